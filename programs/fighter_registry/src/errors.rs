@@ -38,4 +38,52 @@ pub enum GameError {
 
     #[msg("Invalid stamina amount")]
     InvalidStamina,
+
+    #[msg("Caller is not an authorized game server")]
+    UnauthorizedServer,
+
+    #[msg("Only the registry admin can do this")]
+    UnauthorizedAdmin,
+
+    #[msg("Game server already registered")]
+    GameServerAlreadyAdded,
+
+    #[msg("Game server not found")]
+    GameServerNotFound,
+
+    #[msg("Too many game servers")]
+    TooManyGameServers,
+
+    #[msg("Not enough stamina")]
+    InsufficientStamina,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+
+    #[msg("Equipment slot already occupied")]
+    SlotOccupied,
+
+    #[msg("Item cannot be equipped while listed")]
+    ItemListed,
+
+    #[msg("Asset account could not be read")]
+    InvalidAsset,
+
+    #[msg("Island is still locked")]
+    IslandLocked,
+
+    #[msg("Fighter is already on this island")]
+    AlreadyOnIsland,
+
+    #[msg("Travel is on cooldown")]
+    TravelCooldown,
+
+    #[msg("Only the fighter's authority can unequip an item it still owns")]
+    UnequipNotAllowed,
+
+    #[msg("Unequip every item before deleting the fighter")]
+    EquipmentNotEmpty,
+
+    #[msg("Fighter already uses the current account layout")]
+    AlreadyMigrated,
 }
\ No newline at end of file
@@ -1,6 +1,10 @@
 use anchor_lang::prelude::*;
+use anchor_lang::{system_program, Discriminator};
 
-use crate::state::Fighter;
+use mpl_core::{ID as MPL_CORE_ID, accounts::BaseAssetV1};
+use nft_items::{program::Nft, GameItem};
+
+use crate::state::{EffectiveStats, EquippedItem, Fighter, FighterView, LegacyFighter, RegistryConfig};
 use crate::errors::GameError;
 
 
@@ -12,33 +16,207 @@ pub fn initializefighter(ctx: Context<InitializeFighter>, name: String) -> Resul
     require!(!name.is_empty(), GameError::EmptyName);
     require!(name.len() <= 32, GameError::NameTooLong);
 
+    let now = Clock::get()?.unix_timestamp;
+    ctx.accounts.fighter.set_inner(Fighter::new(*ctx.accounts.user.key, name, ctx.bumps.fighter, now));
+    Ok(())
+}
+
+// Grows a fighter created before equipment and islands existed to the current layout. The old stats were
+// set by the owner through updatefighter, so only authority, name and bump carry over and progress restarts
+pub fn migrate_fighter(ctx: Context<MigrateFighter>) -> Result<()> {
+    let fighter = ctx.accounts.fighter.to_account_info();
+    require!(fighter.data_len() == LegacyFighter::SPACE, GameError::AlreadyMigrated);
+    let legacy = {
+        let data = fighter.try_borrow_data()?;
+        require!(data[..8] == Fighter::DISCRIMINATOR, GameError::AlreadyMigrated);
+        LegacyFighter::deserialize(&mut &data[8..])?
+    };
+
+    let space = 8 + Fighter::INIT_SPACE;
+    let top_up = Rent::get()?.minimum_balance(space).saturating_sub(fighter.lamports());
+    if top_up > 0 {
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.authority.to_account_info(),
+            to: fighter.clone(),
+        };
+        system_program::transfer(CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts), top_up)?;
+    }
+    fighter.realloc(space, true)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let migrated = Fighter::new(ctx.accounts.authority.key(), legacy.name, ctx.bumps.fighter, now);
+    let mut data = fighter.try_borrow_mut_data()?;
+    migrated.try_serialize(&mut &mut data[..])
+}
+
+pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.admin = ctx.accounts.admin.key();
+    config.game_servers = Vec::new();
+    config.bump = ctx.bumps.config;
+    Ok(())
+}
+
+pub fn add_game_server(ctx: Context<ManageGameServers>, server: Pubkey) -> Result<()> {
+    let servers = &mut ctx.accounts.config.game_servers;
+    require!(!servers.contains(&server), GameError::GameServerAlreadyAdded);
+    require!(servers.len() < RegistryConfig::MAX_GAME_SERVERS, GameError::TooManyGameServers);
+    servers.push(server);
+    Ok(())
+}
+
+pub fn remove_game_server(ctx: Context<ManageGameServers>, server: Pubkey) -> Result<()> {
+    let servers = &mut ctx.accounts.config.game_servers;
+    let index = servers.iter().position(|key| *key == server).ok_or(GameError::GameServerNotFound)?;
+    servers.swap_remove(index);
+    Ok(())
+}
+
+// Level is always derived from experience, never supplied by the caller
+pub fn grant_experience(ctx: Context<UpdateProgress>, amount: u32) -> Result<()> {
+    require!(amount > 0, GameError::InvalidAmount);
+    ctx.accounts.authorize()?;
+
+    let fighter = &mut ctx.accounts.fighter;
+    fighter.regenerate(Clock::get()?.unix_timestamp);
+    fighter.experience = fighter.experience.checked_add(amount).ok_or(GameError::MathOverflow)?;
+    fighter.level = Fighter::level_for_experience(fighter.experience);
+    fighter.refresh_unlocked_islands();
+    Ok(())
+}
+
+pub fn apply_damage(ctx: Context<UpdateProgress>, amount: u16) -> Result<()> {
+    require!(amount > 0, GameError::InvalidAmount);
+    ctx.accounts.authorize()?;
+
     let fighter = &mut ctx.accounts.fighter;
-    fighter.authority = *ctx.accounts.user.key;
-    fighter.name = name;
-    fighter.health = 100;
-    fighter.stamina = 100;
-    fighter.experience = 0;
-    fighter.level = 1;
-    fighter.tokens = 0;
-    fighter.bump = ctx.bumps.fighter;
+    fighter.regenerate(Clock::get()?.unix_timestamp);
+    fighter.health = fighter.health.saturating_sub(amount);
     Ok(())
 }
 
-pub fn updatefighter(ctx: Context<UpdateFighter>, health: u16, stamina: u16, experience: u32, level: u8, tokens: u64) -> Result<()> {
-    // Custom sanity checks with your errors
-    require!(health <= 1000, GameError::InvalidHealth);
-    require!(level > 0 && level <= 100, GameError::InvalidLevel);
-    require!(stamina <= 200, GameError::InvalidStamina);
+pub fn heal(ctx: Context<UpdateProgress>, amount: u16) -> Result<()> {
+    require!(amount > 0, GameError::InvalidAmount);
+    ctx.accounts.authorize()?;
 
     let fighter = &mut ctx.accounts.fighter;
-    fighter.health = health;
-    fighter.stamina = stamina;
-    fighter.experience = experience;
-    fighter.level = level;
-    fighter.tokens = tokens;
+    fighter.regenerate(Clock::get()?.unix_timestamp);
+    fighter.health = fighter.health.saturating_add(amount).min(fighter.max_health());
+    Ok(())
+}
+
+pub fn consume_stamina(ctx: Context<UpdateProgress>, amount: u16) -> Result<()> {
+    require!(amount > 0, GameError::InvalidAmount);
+    ctx.accounts.authorize()?;
+
+    let fighter = &mut ctx.accounts.fighter;
+    fighter.regenerate(Clock::get()?.unix_timestamp);
+    require!(fighter.stamina >= amount, GameError::InsufficientStamina);
+    fighter.stamina -= amount;
+    Ok(())
+}
+
+// Beating an island's boss opens the next island once the fighter is also high enough level
+pub fn record_boss_defeat(ctx: Context<UpdateProgress>, island: u8) -> Result<()> {
+    let bit = Fighter::island_bit(island)?;
+    ctx.accounts.authorize()?;
+
+    let fighter = &mut ctx.accounts.fighter;
+    require!(fighter.unlocked_islands & bit != 0, GameError::IslandLocked);
+    fighter.defeated_bosses |= bit;
+    fighter.refresh_unlocked_islands();
+    Ok(())
+}
+
+pub fn travel_to_island(ctx: Context<TravelToIsland>, island: u8) -> Result<()> {
+    Fighter::island_bit(island)?;
+    let now = Clock::get()?.unix_timestamp;
+
+    let fighter = &mut ctx.accounts.fighter;
+    fighter.regenerate(now);
+    require!(fighter.is_island_unlocked(island), GameError::IslandLocked);
+    require!(fighter.current_island != island, GameError::AlreadyOnIsland);
+    require!(now >= fighter.last_travel_at + Fighter::TRAVEL_COOLDOWN, GameError::TravelCooldown);
+    require!(fighter.stamina >= Fighter::TRAVEL_STAMINA_COST, GameError::InsufficientStamina);
+
+    fighter.stamina -= Fighter::TRAVEL_STAMINA_COST;
+    fighter.current_island = island;
+    fighter.last_travel_at = now;
+    Ok(())
+}
+
+pub fn equip_item(ctx: Context<EquipItem>) -> Result<()> {
+    let asset = BaseAssetV1::try_from(&ctx.accounts.asset.to_account_info())
+        .map_err(|_| error!(GameError::InvalidAsset))?;
+    require_keys_eq!(asset.owner, ctx.accounts.authority.key(), GameError::NotItemOwner);
+
+    let game_item = &ctx.accounts.game_item;
+    require!(!game_item.is_equipped, GameError::AlreadyEquipped);
+    require!(!game_item.is_listed, GameError::ItemListed);
+    let item_type = game_item.item_type;
+    let equipped = EquippedItem { asset: ctx.accounts.asset.key(), stats: game_item.stats };
+
+    sync_equipped(&ctx.accounts.fighter, &ctx.accounts.nft_items_program, &ctx.accounts.game_item, &ctx.accounts.asset, true)?;
+
+    let slot = ctx.accounts.fighter.equipment.slot_mut(item_type);
+    require!(slot.is_none(), GameError::SlotOccupied);
+    *slot = Some(equipped);
     Ok(())
 }
 
+// Unequipping only needs the slot to hold the asset, so items moved elsewhere can still be released.
+// Once the asset left the fighter's authority anyone may do it, so sold items stop counting towards its stats
+pub fn unequip_item(ctx: Context<UnequipItem>) -> Result<()> {
+    let authority = ctx.accounts.fighter.authority;
+    if ctx.accounts.caller.key() != authority {
+        let asset = BaseAssetV1::try_from(&ctx.accounts.asset.to_account_info())
+            .map_err(|_| error!(GameError::InvalidAsset))?;
+        require_keys_neq!(asset.owner, authority, GameError::UnequipNotAllowed);
+    }
+
+    let item_type = ctx.accounts.game_item.item_type;
+    let asset_key = ctx.accounts.asset.key();
+    let slot = ctx.accounts.fighter.equipment.slot_mut(item_type);
+    require!(slot.is_some_and(|item| item.asset == asset_key), GameError::InvalidItemId);
+    *slot = None;
+
+    sync_equipped(&ctx.accounts.fighter, &ctx.accounts.nft_items_program, &ctx.accounts.game_item, &ctx.accounts.asset, false)
+}
+
+pub fn get_effective_stats(ctx: Context<ViewFighter>) -> Result<EffectiveStats> {
+    Ok(regenerated(&ctx.accounts.fighter)?.effective_stats())
+}
+
+// Read-only: regeneration is applied to a copy and returned, never written back
+pub fn view_fighter(ctx: Context<ViewFighter>) -> Result<FighterView> {
+    Ok(regenerated(&ctx.accounts.fighter)?.view())
+}
+
+fn regenerated(fighter: &Fighter) -> Result<Fighter> {
+    let mut fighter = fighter.clone();
+    fighter.regenerate(Clock::get()?.unix_timestamp);
+    Ok(fighter)
+}
+
+// nft-items trusts the fighter PDA to flip GameItem.is_equipped
+fn sync_equipped<'info>(
+    fighter: &Account<'info, Fighter>,
+    nft_items_program: &Program<'info, Nft>,
+    game_item: &Account<'info, GameItem>,
+    asset: &AccountInfo<'info>,
+    is_equipped: bool,
+) -> Result<()> {
+    let cpi_accounts = nft_items::cpi::accounts::SyncEquippedState {
+        game_item: game_item.to_account_info(),
+        asset: asset.clone(),
+        fighter: fighter.to_account_info(),
+    };
+    let bump = [fighter.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"fighter", fighter.authority.as_ref(), &bump]];
+    let cpi_ctx = CpiContext::new_with_signer(nft_items_program.to_account_info(), cpi_accounts, signer_seeds);
+    nft_items::cpi::sync_equipped_state(cpi_ctx, is_equipped)
+}
+
 pub fn deletefighter(_ctx: Context<DeleteFighter>) -> Result<()> {
     // No-op: Anchor will close the account & refund rent automatically
     Ok(())
@@ -67,16 +245,103 @@ pub struct InitializeFighter<'info> {
 
 
 #[derive(Accounts)]
-pub struct UpdateFighter<'info> {
+pub struct MigrateFighter<'info> {
+    /// CHECK: Legacy fighter, no longer readable as Fighter. Layout and discriminator are checked in the handler
+    #[account(mut, owner = crate::ID, seeds = [b"fighter", authority.key().as_ref()], bump)]
+    pub fighter: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(init, payer = admin, space = 8 + RegistryConfig::INIT_SPACE, seeds = [b"registry_config"], bump)]
+    pub config: Account<'info, RegistryConfig>,
+    // Only the upgrade authority can become registry admin, so the config can't be front-run after deploy
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::FighterRegistry>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ GameError::UnauthorizedAdmin)]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageGameServers<'info> {
+    #[account(mut, has_one = admin @ GameError::UnauthorizedAdmin, seeds = [b"registry_config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    pub admin: Signer<'info>,
+}
+
+// Signed by a game server key registered in the config
+#[derive(Accounts)]
+pub struct UpdateProgress<'info> {
+    #[account(mut, seeds = [b"fighter", fighter.authority.as_ref()], bump = fighter.bump)]
+    pub fighter: Account<'info, Fighter>,
+    #[account(seeds = [b"registry_config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    pub game_server: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TravelToIsland<'info> {
+    #[account(mut, has_one = authority, seeds = [b"fighter", authority.key().as_ref()], bump = fighter.bump)]
+    pub fighter: Account<'info, Fighter>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EquipItem<'info> {
     #[account(mut, has_one = authority, seeds = [b"fighter", authority.key().as_ref()], bump = fighter.bump)]
     pub fighter: Account<'info, Fighter>,
     pub authority: Signer<'info>,
+    /// CHECK: Core asset, owner is read from its data
+    #[account(owner = MPL_CORE_ID)]
+    pub asset: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"game_item", asset.key().as_ref()], bump, seeds::program = nft_items::ID)]
+    pub game_item: Account<'info, GameItem>,
+    pub nft_items_program: Program<'info, Nft>,
+}
+
+#[derive(Accounts)]
+pub struct UnequipItem<'info> {
+    #[account(mut, seeds = [b"fighter", fighter.authority.as_ref()], bump = fighter.bump)]
+    pub fighter: Account<'info, Fighter>,
+    pub caller: Signer<'info>,
+    /// CHECK: Core asset, owner is read from its data
+    #[account(owner = MPL_CORE_ID)]
+    pub asset: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"game_item", asset.key().as_ref()], bump, seeds::program = nft_items::ID)]
+    pub game_item: Account<'info, GameItem>,
+    pub nft_items_program: Program<'info, Nft>,
+}
+
+#[derive(Accounts)]
+pub struct ViewFighter<'info> {
+    #[account(seeds = [b"fighter", fighter.authority.as_ref()], bump = fighter.bump)]
+    pub fighter: Account<'info, Fighter>,
+}
 
+impl UpdateProgress<'_> {
+    fn authorize(&self) -> Result<()> {
+        require!(self.config.game_servers.contains(&self.game_server.key()), GameError::UnauthorizedServer);
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
 pub struct DeleteFighter<'info> {
-    #[account(mut, close = user, has_one = authority, seeds = [b"fighter", authority.key().as_ref()], bump = fighter.bump)]
+    // Equipped items must be released first, their GameItems would otherwise stay flagged as equipped
+    #[account(
+        mut,
+        close = user,
+        has_one = authority,
+        seeds = [b"fighter", authority.key().as_ref()],
+        bump = fighter.bump,
+        constraint = fighter.equipment.items().next().is_none() @ GameError::EquipmentNotEmpty
+    )]
     pub fighter: Account<'info, Fighter>,
     pub authority: Signer<'info>,
     #[account(mut)]
@@ -17,15 +17,60 @@ pub mod fighter_registry {
         instructions::initializefighter(ctx, name)
     }
 
-    pub fn updatefighter(
-        ctx: Context<UpdateFighter>,
-        health: u16,
-        stamina: u16,
-        experience: u32,
-        level: u8,
-        tokens: u64,
-    ) -> Result<()> {
-        instructions::updatefighter(ctx, health, stamina, experience, level, tokens)
+    pub fn migrate_fighter(ctx: Context<MigrateFighter>) -> Result<()> {
+        instructions::migrate_fighter(ctx)
+    }
+
+    pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
+        instructions::initialize_config(ctx)
+    }
+
+    pub fn add_game_server(ctx: Context<ManageGameServers>, server: Pubkey) -> Result<()> {
+        instructions::add_game_server(ctx, server)
+    }
+
+    pub fn remove_game_server(ctx: Context<ManageGameServers>, server: Pubkey) -> Result<()> {
+        instructions::remove_game_server(ctx, server)
+    }
+
+    pub fn grant_experience(ctx: Context<UpdateProgress>, amount: u32) -> Result<()> {
+        instructions::grant_experience(ctx, amount)
+    }
+
+    pub fn apply_damage(ctx: Context<UpdateProgress>, amount: u16) -> Result<()> {
+        instructions::apply_damage(ctx, amount)
+    }
+
+    pub fn heal(ctx: Context<UpdateProgress>, amount: u16) -> Result<()> {
+        instructions::heal(ctx, amount)
+    }
+
+    pub fn consume_stamina(ctx: Context<UpdateProgress>, amount: u16) -> Result<()> {
+        instructions::consume_stamina(ctx, amount)
+    }
+
+    pub fn record_boss_defeat(ctx: Context<UpdateProgress>, island: u8) -> Result<()> {
+        instructions::record_boss_defeat(ctx, island)
+    }
+
+    pub fn travel_to_island(ctx: Context<TravelToIsland>, island: u8) -> Result<()> {
+        instructions::travel_to_island(ctx, island)
+    }
+
+    pub fn equip_item(ctx: Context<EquipItem>) -> Result<()> {
+        instructions::equip_item(ctx)
+    }
+
+    pub fn unequip_item(ctx: Context<UnequipItem>) -> Result<()> {
+        instructions::unequip_item(ctx)
+    }
+
+    pub fn get_effective_stats(ctx: Context<ViewFighter>) -> Result<state::EffectiveStats> {
+        instructions::get_effective_stats(ctx)
+    }
+
+    pub fn view_fighter(ctx: Context<ViewFighter>) -> Result<state::FighterView> {
+        instructions::view_fighter(ctx)
     }
 
     pub fn deletefighter(ctx: Context<DeleteFighter>) -> Result<()> {
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use nft_items::{ItemStats, ItemType};
 
 
 
@@ -18,5 +19,281 @@ pub struct Fighter {
     pub experience: u32,         
     pub level: u8,    
     pub tokens : u64,
+    pub equipment: Equipment,
+    pub current_island: u8,      // 1-4
+    pub unlocked_islands: u8,    // Bit i-1 set once island i is unlocked
+    pub defeated_bosses: u8,     // Bit i-1 set once the boss of island i is beaten
+    // Everything above is mirrored by nft_items::FighterProgress for treasury drops;
+    // keep both in sync (see the tests at the bottom of this file)
+    pub last_travel_at: i64,
+    pub health_updated_at: i64,  // Health and stamina regenerate lazily from these timestamps
+    pub stamina_updated_at: i64,
     pub bump : u8,           
-}
\ No newline at end of file
+}
+
+impl Fighter {
+    pub const MAX_LEVEL: u8 = 100;
+    pub const BASE_HEALTH: u16 = 100;
+    pub const BASE_STAMINA: u16 = 100;
+    pub const HEALTH_REGEN_SECONDS: i64 = 60;  // 1 health per minute
+    pub const STAMINA_REGEN_SECONDS: i64 = 30; // 1 stamina per 30 seconds
+
+    pub const ISLAND_COUNT: u8 = 4;
+    pub const ALL_ISLANDS: u8 = (1 << Self::ISLAND_COUNT) - 1;
+    // Minimum level for islands 1-4; each island past the first also needs the previous boss beaten
+    pub const ISLAND_MIN_LEVEL: [u8; 4] = [1, 5, 10, 20];
+    pub const TRAVEL_STAMINA_COST: u16 = 20;
+    pub const TRAVEL_COOLDOWN: i64 = 600;
+
+    // Fresh level 1 fighter on island 1 with full health and stamina
+    pub fn new(authority: Pubkey, name: String, bump: u8, now: i64) -> Self {
+        let mut fighter = Fighter {
+            authority,
+            name,
+            health: Self::BASE_HEALTH,
+            stamina: Self::BASE_STAMINA,
+            experience: 0,
+            level: 1,
+            tokens: 0,
+            equipment: Equipment::default(),
+            current_island: 1,
+            unlocked_islands: 0,
+            defeated_bosses: 0,
+            last_travel_at: 0,
+            health_updated_at: now,
+            stamina_updated_at: now,
+            bump,
+        };
+        fighter.refresh_unlocked_islands();
+        fighter
+    }
+
+    pub fn island_bit(island: u8) -> Result<u8> {
+        require!((1..=Self::ISLAND_COUNT).contains(&island), crate::errors::GameError::InvalidIsland);
+        Ok(1 << (island - 1))
+    }
+
+    pub fn is_island_unlocked(&self, island: u8) -> bool {
+        Self::island_bit(island).is_ok_and(|bit| self.unlocked_islands & bit != 0)
+    }
+
+    // Unlocks are never revoked, so this only ever adds bits
+    pub fn refresh_unlocked_islands(&mut self) {
+        for island in 1..=Self::ISLAND_COUNT {
+            let index = (island - 1) as usize;
+            let previous_boss_beaten = island == 1 || self.defeated_bosses & (1 << (index - 1)) != 0;
+            if self.level >= Self::ISLAND_MIN_LEVEL[index] && previous_boss_beaten {
+                self.unlocked_islands |= 1 << index;
+            }
+        }
+    }
+
+    pub fn all_islands_conquered(&self) -> bool {
+        self.defeated_bosses == Self::ALL_ISLANDS
+    }
+
+    // XP curve: reaching level n takes 100 * (n - 1)^2 experience
+    pub fn level_for_experience(experience: u32) -> u8 {
+        let mut level: u32 = 1;
+        while level < Self::MAX_LEVEL as u32 && 100 * level * level <= experience {
+            level += 1;
+        }
+        level as u8
+    }
+
+    // Each level above 1 adds 5 max health
+    pub fn max_health(&self) -> u16 {
+        Self::BASE_HEALTH + 5 * (self.level as u16 - 1)
+    }
+
+    // Each level above 1 adds 2 max stamina
+    pub fn max_stamina(&self) -> u16 {
+        Self::BASE_STAMINA + 2 * (self.level as u16 - 1)
+    }
+
+    // Applies regeneration accrued since the last update; run before reading or changing health/stamina
+    pub fn regenerate(&mut self, now: i64) {
+        let max_health = self.max_health();
+        let max_stamina = self.max_stamina();
+        regen(&mut self.health, &mut self.health_updated_at, max_health, Self::HEALTH_REGEN_SECONDS, now);
+        regen(&mut self.stamina, &mut self.stamina_updated_at, max_stamina, Self::STAMINA_REGEN_SECONDS, now);
+    }
+
+    pub fn view(&self) -> FighterView {
+        FighterView {
+            level: self.level,
+            experience: self.experience,
+            health: self.health,
+            max_health: self.max_health(),
+            stamina: self.stamina,
+            max_stamina: self.max_stamina(),
+            current_island: self.current_island,
+            unlocked_islands: self.unlocked_islands,
+            defeated_bosses: self.defeated_bosses,
+        }
+    }
+
+    pub fn effective_stats(&self) -> EffectiveStats {
+        let mut stats = EffectiveStats {
+            level: self.level,
+            health: self.health,
+            max_health: self.max_health(),
+            stamina: self.stamina,
+            attack_power: 0,
+            defense: 0,
+            speed_boost: 0,
+        };
+        for item in self.equipment.items() {
+            stats.attack_power = stats.attack_power.saturating_add(item.stats.attack_power);
+            stats.defense = stats.defense.saturating_add(item.stats.defense);
+            stats.speed_boost = stats.speed_boost.saturating_add(item.stats.speed_boost);
+        }
+        stats
+    }
+}
+
+// Fighter layout before equipment, islands and regeneration were added, only read by migrate_fighter
+#[derive(AnchorDeserialize)]
+#[allow(dead_code)] // Only read for layout
+pub struct LegacyFighter {
+    pub authority: Pubkey,
+    pub name: String,
+    pub health: u16,
+    pub stamina: u16,
+    pub experience: u32,
+    pub level: u8,
+    pub tokens: u64,
+    pub bump: u8,
+}
+
+impl LegacyFighter {
+    pub const SPACE: usize = 8 + 32 + (4 + 32) + 2 + 2 + 4 + 1 + 8 + 1; // 94 bytes
+}
+
+// Partial intervals carry over; a full stat restarts the clock so regen counts from the next loss
+fn regen(value: &mut u16, updated_at: &mut i64, max: u16, interval: i64, now: i64) {
+    if *value >= max {
+        *updated_at = now;
+        return;
+    }
+    let points = (now - *updated_at).max(0) / interval;
+    let gained = points.min((max - *value) as i64) as u16;
+    *value += gained;
+    *updated_at = if *value >= max { now } else { *updated_at + points * interval };
+}
+
+// One slot per ItemType
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, InitSpace)]
+pub struct Equipment {
+    pub weapon: Option<EquippedItem>,
+    pub ship: Option<EquippedItem>,
+    pub tool: Option<EquippedItem>,
+    pub artifact: Option<EquippedItem>,
+    pub cosmetic: Option<EquippedItem>,
+}
+
+// Item stats are snapshotted when the item is equipped
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct EquippedItem {
+    pub asset: Pubkey,
+    pub stats: ItemStats,
+}
+
+impl Equipment {
+    pub fn slot_mut(&mut self, item_type: ItemType) -> &mut Option<EquippedItem> {
+        match item_type {
+            ItemType::Weapon => &mut self.weapon,
+            ItemType::Ship => &mut self.ship,
+            ItemType::Tool => &mut self.tool,
+            ItemType::Artifact => &mut self.artifact,
+            ItemType::Cosmetic => &mut self.cosmetic,
+        }
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &EquippedItem> {
+        [&self.weapon, &self.ship, &self.tool, &self.artifact, &self.cosmetic]
+            .into_iter()
+            .flatten()
+    }
+}
+
+// Base fighter stats plus the bonuses of everything equipped
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EffectiveStats {
+    pub level: u8,
+    pub health: u16,
+    pub max_health: u16,
+    pub stamina: u16,
+    pub attack_power: u16,
+    pub defense: u16,
+    pub speed_boost: u16,
+}
+
+// Current fighter values with regeneration applied
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct FighterView {
+    pub level: u8,
+    pub experience: u32,
+    pub health: u16,
+    pub max_health: u16,
+    pub stamina: u16,
+    pub max_stamina: u16,
+    pub current_island: u8,
+    pub unlocked_islands: u8,
+    pub defeated_bosses: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RegistryConfig {
+    pub admin: Pubkey,
+    #[max_len(8)]
+    pub game_servers: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl RegistryConfig {
+    pub const MAX_GAME_SERVERS: usize = 8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nft_items::FighterProgress;
+
+    #[test]
+    fn fighter_progress_mirror_matches_fighter_layout() {
+        let authority = Pubkey::new_unique();
+        let asset = Pubkey::new_unique();
+        let mut fighter = Fighter::new(authority, "Luffy".to_string(), 254, 1_700_000_000);
+        fighter.experience = 36_100;
+        fighter.tokens = 42;
+        fighter.current_island = 3;
+        fighter.defeated_bosses = Fighter::ALL_ISLANDS;
+        *fighter.equipment.slot_mut(ItemType::Artifact) = Some(EquippedItem {
+            asset,
+            stats: ItemStats { attack_power: 10, defense: 20, speed_boost: 30, special_ability: 4 },
+        });
+        fighter.refresh_unlocked_islands();
+
+        let mut data = Vec::new();
+        fighter.try_serialize(&mut data).unwrap();
+        let progress = FighterProgress::try_from_data(&data).unwrap();
+
+        assert_eq!(progress.authority, authority);
+        assert_eq!(progress.name, "Luffy");
+        assert_eq!(progress.health, fighter.health);
+        assert_eq!(progress.stamina, fighter.stamina);
+        assert_eq!(progress.experience, 36_100);
+        assert_eq!(progress.level, fighter.level);
+        assert_eq!(progress.tokens, 42);
+        let artifact = progress.equipment[ItemType::Artifact as usize].as_ref().unwrap();
+        assert_eq!(artifact.asset, asset);
+        assert_eq!(artifact.stats.special_ability, 4);
+        assert_eq!(progress.equipment.iter().flatten().count(), 1);
+        assert_eq!(progress.current_island, 3);
+        assert_eq!(progress.unlocked_islands, fighter.unlocked_islands);
+        assert_eq!(progress.defeated_bosses, Fighter::ALL_ISLANDS);
+        assert_eq!(FighterProgress::ALL_ISLANDS, Fighter::ALL_ISLANDS);
+    }
+}
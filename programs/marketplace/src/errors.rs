@@ -6,6 +6,64 @@ pub enum MarketplaceError {
     ListingNotActive,
     #[msg("Only the seller can update or cancel listing")]
     Unauthorized,
-    #[msg("Invalid PIRATE mint")]
-    InvalidPirateMint,
+    #[msg("Payment mint is not accepted by the marketplace")]
+    UnsupportedPaymentMint,
+    #[msg("NFT mint must have zero decimals")]
+    InvalidNftMint,
+    #[msg("Listing is for a different asset standard")]
+    WrongAssetStandard,
+    #[msg("Core asset account could not be read")]
+    InvalidAsset,
+    #[msg("Seller does not own this asset")]
+    NotAssetOwner,
+    #[msg("Only the marketplace admin can change the config")]
+    NotMarketplaceAdmin,
+    #[msg("Protocol fee exceeds the maximum basis points")]
+    FeeTooHigh,
+    #[msg("Protocol fee and royalty exceed the sale price")]
+    FeesExceedPrice,
+    #[msg("Fee treasury account does not belong to the configured treasury")]
+    InvalidFeeTreasury,
+    #[msg("Royalty recipient accounts do not match the creators")]
+    InvalidRoyaltyRecipient,
+    #[msg("Collection does not match the asset")]
+    CollectionMismatch,
+    #[msg("Calculation overflow")]
+    MathOverflow,
+    #[msg("Invalid auction prices or duration")]
+    InvalidAuctionParams,
+    #[msg("Instruction does not apply to this auction type")]
+    WrongAuctionKind,
+    #[msg("Auction has already ended")]
+    AuctionEnded,
+    #[msg("Auction has not ended yet")]
+    AuctionNotEnded,
+    #[msg("Bid is below the minimum required")]
+    BidTooLow,
+    #[msg("Seller cannot bid on their own auction")]
+    SellerCannotBid,
+    #[msg("Auction already has bids")]
+    AuctionHasBids,
+    #[msg("Bidder token account is missing or does not match")]
+    InvalidBidderAccount,
+    #[msg("Offer amount must be greater than zero")]
+    InvalidOfferAmount,
+    #[msg("Item does not match the collection bid")]
+    ItemDoesNotMatchBid,
+    #[msg("Item is equipped or already listed")]
+    ItemUnavailable,
+    #[msg("Payment account does not match the recipient or payment mint")]
+    InvalidPaymentAccount,
+    #[msg("Too many payment mints")]
+    TooManyPaymentMints,
+    #[msg("Bundle must hold between one and the maximum number of items")]
+    InvalidBundleSize,
+    #[msg("Bundle item accounts are missing or do not match the bundle")]
+    BundleItemMismatch,
+    #[msg("Listing expiry must be in the future")]
+    InvalidExpiry,
+    #[msg("Listing has expired")]
+    ListingExpired,
+    #[msg("Listing has not expired yet")]
+    ListingNotExpired,
 }
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct ListingCreated {
+    pub listing: Pubkey,
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+    pub payment_mint: Pubkey,
+    pub expires_at: Option<i64>,
+    pub slot: u64,
+}
+
+#[event]
+pub struct ListingUpdated {
+    pub listing: Pubkey,
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+    pub is_active: bool,
+    pub slot: u64,
+}
+
+// Emitted when the seller cancels or an expired listing is cranked closed, per item for bundles,
+// and for auctions that are cancelled or end without meeting the reserve
+#[event]
+pub struct ListingCancelled {
+    pub listing: Pubkey,
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+    pub expired: bool,
+    pub slot: u64,
+}
+
+// Every completed sale: fixed-price listings, auctions, offers, collection bids and each item of a bundle
+#[event]
+pub struct Sale {
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub payment_mint: Pubkey,
+    pub protocol_fee: u64,
+    pub royalty: u64,
+    pub slot: u64,
+}
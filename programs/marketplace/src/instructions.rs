@@ -1,22 +1,77 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, Transfer};
+use anchor_lang::system_program;
+use anchor_spl::associated_token::{self, get_associated_token_address, AssociatedToken};
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Mint, Transfer};
+use mpl_core::{
+    ID as MPL_CORE_ID,
+    accounts::{BaseAssetV1, BaseCollectionV1},
+    fetch_plugin,
+    instructions::{
+        AddPluginV1CpiBuilder, ApprovePluginAuthorityV1CpiBuilder, RevokePluginAuthorityV1CpiBuilder,
+        TransferV1CpiBuilder, UpdatePluginV1CpiBuilder,
+    },
+    list_plugins,
+    types::{FreezeDelegate, Plugin, PluginAuthority, PluginType, Royalties, TransferDelegate, UpdateAuthority},
+};
+use nft_items::{program::Nft, GameItem, ItemType};
 use crate::states::*;
 use crate::errors::*;
-use std::str::FromStr;
+use crate::events::*;
 
-// Paste your PIRATE token mint address here
-pub const PIRATE_TOKEN_MINT: &str = "euGobpfkxFW7tqgkTvet5qT5qLDEYcECCddaT7DXUuk";
+pub fn initialize_marketplace(ctx: Context<InitializeMarketplace>, fee_bps: u16, payment_mints: Vec<Pubkey>) -> Result<()> {
+    require!(fee_bps <= MarketplaceConfig::MAX_FEE_BPS, MarketplaceError::FeeTooHigh);
+    require!(payment_mints.len() <= MarketplaceConfig::MAX_PAYMENT_MINTS, MarketplaceError::TooManyPaymentMints);
+
+    let config = &mut ctx.accounts.config;
+    config.admin = ctx.accounts.admin.key();
+    config.fee_bps = fee_bps;
+    config.fee_treasury = ctx.accounts.fee_treasury.key();
+    config.bump = ctx.bumps.config;
+    config.payment_mints = payment_mints;
+    Ok(())
+}
+
+pub fn update_fee_config(ctx: Context<UpdateFeeConfig>, fee_bps: u16) -> Result<()> {
+    require!(fee_bps <= MarketplaceConfig::MAX_FEE_BPS, MarketplaceError::FeeTooHigh);
+
+    let config = &mut ctx.accounts.config;
+    config.fee_bps = fee_bps;
+    config.fee_treasury = ctx.accounts.fee_treasury.key();
+    Ok(())
+}
+
+// Replaces the accepted mints. Existing listings in a removed mint can no longer be bought until it is re-added
+pub fn set_payment_mints(ctx: Context<SetPaymentMints>, payment_mints: Vec<Pubkey>) -> Result<()> {
+    require!(payment_mints.len() <= MarketplaceConfig::MAX_PAYMENT_MINTS, MarketplaceError::TooManyPaymentMints);
+    ctx.accounts.config.payment_mints = payment_mints;
+    Ok(())
+}
+
+pub fn list_nft(ctx: Context<ListNft>, price: u64, payment_mint: Pubkey, expires_at: Option<i64>) -> Result<()> {
+    require!(ctx.accounts.nft_mint.decimals == 0, MarketplaceError::InvalidNftMint);
+    require!(ctx.accounts.config.accepts(&payment_mint), MarketplaceError::UnsupportedPaymentMint);
+    let now = Clock::get()?.unix_timestamp;
+    require!(expires_at.map_or(true, |expires_at| expires_at > now), MarketplaceError::InvalidExpiry);
 
-pub fn list_nft(ctx: Context<ListNft>, price: u64) -> Result<()> {
     let listing = &mut ctx.accounts.listing;
     listing.nft_mint = ctx.accounts.nft_mint.key();
     listing.seller = ctx.accounts.seller.key();
     listing.price = price;
     listing.is_active = true;
     listing.bump = ctx.bumps.listing;
-    Ok(())
-
+    listing.standard = AssetStandard::SplToken;
+    listing.payment_mint = payment_mint;
+    listing.expires_at = expires_at;
 
+    // Move the NFT into the listing's escrow so it can't be moved while listed
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.seller_nft_token.to_account_info(),
+        to: ctx.accounts.escrow_nft_token.to_account_info(),
+        authority: ctx.accounts.seller.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+    token::transfer(cpi_ctx, 1)?;
+    emit_listing_created(&ctx.accounts.listing)
 }
 
 pub fn update_listing(ctx: Context<UpdateListing>, new_price: u64, is_active: bool) -> Result<()> {
@@ -24,55 +79,1273 @@ pub fn update_listing(ctx: Context<UpdateListing>, new_price: u64, is_active: bo
     require_keys_eq!(listing.seller, ctx.accounts.seller.key(), MarketplaceError::Unauthorized);
     listing.price = new_price;
     listing.is_active = is_active;
+
+    emit!(ListingUpdated {
+        listing: listing.key(),
+        mint: listing.nft_mint,
+        seller: listing.seller,
+        price: new_price,
+        is_active,
+        slot: Clock::get()?.slot,
+    });
     Ok(())
 }
 
 
 pub fn cancel_listing(ctx: Context<CancelListing>) -> Result<()> {
-    let listing = &mut ctx.accounts.listing;
+    let listing = &ctx.accounts.listing;
     require_keys_eq!(listing.seller, ctx.accounts.seller.key(), MarketplaceError::Unauthorized);
-    listing.is_active = false;
-    Ok(())
+    require!(listing.standard == AssetStandard::SplToken, MarketplaceError::WrongAssetStandard);
+
+    let bump = [listing.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"listing", listing.nft_mint.as_ref(), &bump]];
+
+    // Return the escrowed NFT to the seller
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.escrow_nft_token.to_account_info(),
+        to: ctx.accounts.seller_nft_token.to_account_info(),
+        authority: listing.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    token::transfer(cpi_ctx, 1)?;
+
+    let close_accounts = CloseAccount {
+        account: ctx.accounts.escrow_nft_token.to_account_info(),
+        destination: ctx.accounts.seller.to_account_info(),
+        authority: listing.to_account_info(),
+    };
+    let close_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), close_accounts, signer_seeds);
+    token::close_account(close_ctx)?;
+    emit_listing_cancelled(listing, false)
 }
 
 
 pub fn buy_nft(ctx: Context<BuyNft>) -> Result<()> {
+    let listing = &ctx.accounts.listing;
+    require!(listing.is_active, MarketplaceError::ListingNotActive);
+    require!(listing.standard == AssetStandard::SplToken, MarketplaceError::WrongAssetStandard);
+    require!(!listing.is_expired(Clock::get()?.unix_timestamp), MarketplaceError::ListingExpired);
+    require!(ctx.accounts.config.accepts(&listing.payment_mint), MarketplaceError::UnsupportedPaymentMint);
+
+    // SPL mints carry no Core royalty plugin, so only the protocol fee is taken
+    let source = PaymentSource::buyer(
+        listing.payment_mint,
+        &ctx.accounts.buyer,
+        ctx.accounts.buyer_payment_token.as_ref(),
+        &ctx.accounts.token_program,
+        &ctx.accounts.system_program,
+    )?;
+    let split = pay_sale(
+        &source,
+        &ctx.accounts.config,
+        &ctx.accounts.fee_treasury,
+        &ctx.accounts.seller_payment,
+        listing.seller,
+        listing.price,
+        None,
+        &[],
+    )?;
+    record_sale(ctx.accounts.price_history.as_mut(), listing.nft_mint, listing.seller, ctx.accounts.buyer.key(), listing.price, listing.payment_mint, &split)?;
+
+    // Release the NFT from escrow, signed by the listing PDA
+    let bump = [listing.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"listing", listing.nft_mint.as_ref(), &bump]];
+
+    let nft_cpi_accounts = Transfer {
+        from: ctx.accounts.escrow_nft_token.to_account_info(),
+        to: ctx.accounts.buyer_nft_token.to_account_info(),
+        authority: listing.to_account_info(),
+    };
+    let cpi_ctx_nft = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), nft_cpi_accounts, signer_seeds);
+    token::transfer(cpi_ctx_nft, 1)?; // Always 1 for NFT
+
+    // Empty escrow rent goes back to the seller, the listing itself is closed by Anchor
+    let close_accounts = CloseAccount {
+        account: ctx.accounts.escrow_nft_token.to_account_info(),
+        destination: ctx.accounts.seller.to_account_info(),
+        authority: listing.to_account_info(),
+    };
+    let close_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), close_accounts, signer_seeds);
+    token::close_account(close_ctx)?;
+    Ok(())
+}
+
+// Permissionless crank: anyone can unwind an expired listing, the NFT and all rent go back to the seller
+pub fn close_expired_listing(ctx: Context<CloseExpiredListing>) -> Result<()> {
+    let listing = &ctx.accounts.listing;
+    require!(listing.standard == AssetStandard::SplToken, MarketplaceError::WrongAssetStandard);
+    require!(listing.is_expired(Clock::get()?.unix_timestamp), MarketplaceError::ListingNotExpired);
+
+    let bump = [listing.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"listing", listing.nft_mint.as_ref(), &bump]];
+    let listing_info = listing.to_account_info();
+    transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.escrow_nft_token, &ctx.accounts.seller_nft_token, &listing_info, 1, signer_seeds)?;
+    close_escrow(&ctx.accounts.token_program, &ctx.accounts.escrow_nft_token, &ctx.accounts.seller, &listing_info, signer_seeds)?;
+    emit_listing_cancelled(listing, true)
+}
+
+pub fn list_core_asset(ctx: Context<ListCoreAsset>, price: u64, payment_mint: Pubkey, expires_at: Option<i64>) -> Result<()> {
+    require!(ctx.accounts.config.accepts(&payment_mint), MarketplaceError::UnsupportedPaymentMint);
+    let now = Clock::get()?.unix_timestamp;
+    require!(expires_at.map_or(true, |expires_at| expires_at > now), MarketplaceError::InvalidExpiry);
+    let asset = BaseAssetV1::try_from(&ctx.accounts.asset.to_account_info())
+        .map_err(|_| error!(MarketplaceError::InvalidAsset))?;
+    require_keys_eq!(asset.owner, ctx.accounts.seller.key(), MarketplaceError::NotAssetOwner);
+
     let listing = &mut ctx.accounts.listing;
+    listing.nft_mint = ctx.accounts.asset.key();
+    listing.seller = ctx.accounts.seller.key();
+    listing.price = price;
+    listing.is_active = true;
+    listing.bump = ctx.bumps.listing;
+    listing.standard = AssetStandard::Core;
+    listing.payment_mint = payment_mint;
+    listing.expires_at = expires_at;
+
+    // Freeze the asset in the seller's wallet and let only the listing PDA thaw and move it
+    let listing_key = listing.key();
+    let collection = ctx.accounts.collection.as_ref().map(|c| c.to_account_info());
+    let plugins = [
+        Plugin::FreezeDelegate(FreezeDelegate { frozen: true }),
+        Plugin::TransferDelegate(TransferDelegate {}),
+    ];
+    for plugin in plugins {
+        delegate_core_plugin(&ctx.accounts.mpl_core_program, &ctx.accounts.asset, collection.as_ref(), &ctx.accounts.seller, &ctx.accounts.system_program, plugin, listing_key)?;
+    }
+
+    let bump = [listing.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"listing", listing.nft_mint.as_ref(), &bump]];
+    sync_game_item(&ctx.accounts.nft_items_program, &ctx.accounts.game_item.to_account_info(), &ctx.accounts.asset, &listing.to_account_info(), signer_seeds, true)?;
+    emit_listing_created(listing)
+}
+
+// Remaining accounts: one payment account per royalty creator, in creator order
+// (the creator's token account for the payment mint, or the creator wallet for native SOL)
+pub fn buy_core_asset<'info>(ctx: Context<'_, '_, 'info, 'info, BuyCoreAsset<'info>>) -> Result<()> {
+    let listing = &ctx.accounts.listing;
     require!(listing.is_active, MarketplaceError::ListingNotActive);
-    require_keys_eq!(ctx.accounts.pirate_token_mint.key(), Pubkey::from_str(PIRATE_TOKEN_MINT).unwrap(), MarketplaceError::InvalidPirateMint);
-    
-    
+    require!(listing.standard == AssetStandard::Core, MarketplaceError::WrongAssetStandard);
+    require!(!listing.is_expired(Clock::get()?.unix_timestamp), MarketplaceError::ListingExpired);
+    require!(ctx.accounts.config.accepts(&listing.payment_mint), MarketplaceError::UnsupportedPaymentMint);
+
+    let collection = ctx.accounts.collection.as_ref().map(|c| c.to_account_info());
+    let royalties = core_royalties(&ctx.accounts.asset.to_account_info(), collection.as_ref())?;
+    let source = PaymentSource::buyer(
+        listing.payment_mint,
+        &ctx.accounts.buyer,
+        ctx.accounts.buyer_payment_token.as_ref(),
+        &ctx.accounts.token_program,
+        &ctx.accounts.system_program,
+    )?;
+    let split = pay_sale(
+        &source,
+        &ctx.accounts.config,
+        &ctx.accounts.fee_treasury,
+        &ctx.accounts.seller_payment,
+        listing.seller,
+        listing.price,
+        royalties.as_ref(),
+        ctx.remaining_accounts,
+    )?;
+    record_sale(ctx.accounts.price_history.as_mut(), listing.nft_mint, listing.seller, ctx.accounts.buyer.key(), listing.price, listing.payment_mint, &split)?;
+
+    let bump = [listing.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"listing", listing.nft_mint.as_ref(), &bump]];
+
+    thaw_core_asset(&ctx.accounts.mpl_core_program, &ctx.accounts.asset, collection.as_ref(), &ctx.accounts.buyer, &listing.to_account_info(), &ctx.accounts.system_program, signer_seeds)?;
+
+    // Core resets owner-managed plugin authorities to the new owner on transfer
+    TransferV1CpiBuilder::new(&ctx.accounts.mpl_core_program.to_account_info())
+        .asset(&ctx.accounts.asset.to_account_info())
+        .collection(collection.as_ref())
+        .payer(&ctx.accounts.buyer.to_account_info())
+        .authority(Some(&listing.to_account_info()))
+        .new_owner(&ctx.accounts.buyer.to_account_info())
+        .system_program(Some(&ctx.accounts.system_program.to_account_info()))
+        .invoke_signed(signer_seeds)?;
+
+    sync_game_item(&ctx.accounts.nft_items_program, &ctx.accounts.game_item.to_account_info(), &ctx.accounts.asset, &listing.to_account_info(), signer_seeds, false)
+}
+
+pub fn cancel_core_listing(ctx: Context<CancelCoreListing>) -> Result<()> {
+    let listing = &ctx.accounts.listing;
+    require!(listing.standard == AssetStandard::Core, MarketplaceError::WrongAssetStandard);
+
+    let bump = [listing.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"listing", listing.nft_mint.as_ref(), &bump]];
+    let collection = ctx.accounts.collection.as_ref().map(|c| c.to_account_info());
+
+    release_core_asset(&ctx.accounts.mpl_core_program, &ctx.accounts.asset, collection.as_ref(), &ctx.accounts.seller, &listing.to_account_info(), &ctx.accounts.system_program, signer_seeds)?;
+
+    sync_game_item(&ctx.accounts.nft_items_program, &ctx.accounts.game_item.to_account_info(), &ctx.accounts.asset, &listing.to_account_info(), signer_seeds, false)?;
+    emit_listing_cancelled(listing, false)
+}
+
+// Core counterpart of close_expired_listing: thaws the asset in the seller's wallet and drops the listing's delegates
+pub fn close_expired_core_listing(ctx: Context<CloseExpiredCoreListing>) -> Result<()> {
+    let listing = &ctx.accounts.listing;
+    require!(listing.standard == AssetStandard::Core, MarketplaceError::WrongAssetStandard);
+    require!(listing.is_expired(Clock::get()?.unix_timestamp), MarketplaceError::ListingNotExpired);
+
+    let bump = [listing.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"listing", listing.nft_mint.as_ref(), &bump]];
+    let collection = ctx.accounts.collection.as_ref().map(|c| c.to_account_info());
+
+    release_core_asset(&ctx.accounts.mpl_core_program, &ctx.accounts.asset, collection.as_ref(), &ctx.accounts.cranker, &listing.to_account_info(), &ctx.accounts.system_program, signer_seeds)?;
+
+    sync_game_item(&ctx.accounts.nft_items_program, &ctx.accounts.game_item.to_account_info(), &ctx.accounts.asset, &listing.to_account_info(), signer_seeds, false)?;
+    emit_listing_cancelled(listing, true)
+}
+
+pub fn create_auction(
+    ctx: Context<CreateAuction>,
+    kind: AuctionKind,
+    start_price: u64,
+    reserve_price: u64,
+    min_increment: u64,
+    duration: i64,
+    extension_window: i64,
+) -> Result<()> {
+    require!(ctx.accounts.nft_mint.decimals == 0, MarketplaceError::InvalidNftMint);
+    require!(ctx.accounts.config.accepts(&ctx.accounts.payment_mint.key()), MarketplaceError::UnsupportedPaymentMint);
+    require!(duration > 0 && extension_window >= 0, MarketplaceError::InvalidAuctionParams);
+    if kind == AuctionKind::Dutch {
+        require!(start_price > 0 && start_price >= reserve_price, MarketplaceError::InvalidAuctionParams);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let auction = &mut ctx.accounts.auction;
+    auction.nft_mint = ctx.accounts.nft_mint.key();
+    auction.seller = ctx.accounts.seller.key();
+    auction.kind = kind;
+    auction.start_price = start_price;
+    auction.reserve_price = reserve_price;
+    auction.min_increment = min_increment;
+    auction.start_time = now;
+    auction.end_time = now.checked_add(duration).ok_or(MarketplaceError::MathOverflow)?;
+    auction.extension_window = extension_window;
+    auction.highest_bid = 0;
+    auction.highest_bidder = Pubkey::default();
+    auction.bump = ctx.bumps.auction;
+
     let cpi_accounts = Transfer {
-        from: ctx.accounts.buyer_pirate_token.to_account_info(),
-        to: ctx.accounts.seller_pirate_token.to_account_info(),
-        authority: ctx.accounts.buyer.to_account_info(),
+        from: ctx.accounts.seller_nft_token.to_account_info(),
+        to: ctx.accounts.nft_escrow.to_account_info(),
+        authority: ctx.accounts.seller.to_account_info(),
     };
     let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
-    token::transfer(cpi_ctx, listing.price)?;
+    token::transfer(cpi_ctx, 1)?;
 
-    let nft_cpi_accounts = Transfer {
-        from: ctx.accounts.seller_nft_token.to_account_info(),
-        to: ctx.accounts.buyer_nft_token.to_account_info(),
-        authority: ctx.accounts.seller.to_account_info(),
+    // Indexed like a listing at the opening price. Dutch auctions stay buyable at the floor after end_time
+    let auction = &ctx.accounts.auction;
+    emit!(ListingCreated {
+        listing: auction.key(),
+        mint: auction.nft_mint,
+        seller: auction.seller,
+        price: auction.start_price,
+        payment_mint: ctx.accounts.payment_mint.key(),
+        expires_at: (auction.kind == AuctionKind::English).then_some(auction.end_time),
+        slot: Clock::get()?.slot,
+    });
+    Ok(())
+}
+
+pub fn place_bid(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let auction = &ctx.accounts.auction;
+    require!(auction.kind == AuctionKind::English, MarketplaceError::WrongAuctionKind);
+    require!(now < auction.end_time, MarketplaceError::AuctionEnded);
+    require_keys_neq!(ctx.accounts.bidder.key(), auction.seller, MarketplaceError::SellerCannotBid);
+
+    let min_bid = if auction.has_bid() {
+        auction.highest_bid.checked_add(auction.min_increment.max(1)).ok_or(MarketplaceError::MathOverflow)?
+    } else {
+        auction.start_price.max(1)
     };
-    let cpi_ctx_nft = CpiContext::new(ctx.accounts.token_program.to_account_info(), nft_cpi_accounts);
-    token::transfer(cpi_ctx_nft, 1)?; // Always 1 for NFT
+    require!(amount >= min_bid, MarketplaceError::BidTooLow);
+
+    transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.bidder_payment_token, &ctx.accounts.bid_escrow, &ctx.accounts.bidder.to_account_info(), amount, &[])?;
+
+    // Refund the bidder being outbid
+    if auction.has_bid() {
+        let previous = ctx.accounts.previous_bidder_token.as_ref().ok_or(MarketplaceError::InvalidBidderAccount)?;
+        require_keys_eq!(previous.owner, auction.highest_bidder, MarketplaceError::InvalidBidderAccount);
+
+        let bump = [auction.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"auction", auction.nft_mint.as_ref(), &bump]];
+        transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.bid_escrow, previous, &auction.to_account_info(), auction.highest_bid, signer_seeds)?;
+    }
+
+    let auction = &mut ctx.accounts.auction;
+    auction.highest_bid = amount;
+    auction.highest_bidder = ctx.accounts.bidder.key();
+    // Anti-sniping: late bids keep the auction open for another window
+    if auction.end_time - now < auction.extension_window {
+        auction.end_time = now.checked_add(auction.extension_window).ok_or(MarketplaceError::MathOverflow)?;
+    }
+    Ok(())
+}
+
+// Permissionless once the English auction ends: sells to the winner if the reserve is met, otherwise unwinds
+pub fn settle_auction(ctx: Context<SettleAuction>) -> Result<()> {
+    let auction = &ctx.accounts.auction;
+    require!(auction.kind == AuctionKind::English, MarketplaceError::WrongAuctionKind);
+    require!(Clock::get()?.unix_timestamp >= auction.end_time, MarketplaceError::AuctionNotEnded);
+
+    let bump = [auction.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"auction", auction.nft_mint.as_ref(), &bump]];
+    let auction_info = auction.to_account_info();
+
+    let nft_destination = if auction.has_bid() && auction.highest_bid >= auction.reserve_price {
+        let winner_nft_token = ctx.accounts.winner_nft_token.as_ref().ok_or(MarketplaceError::InvalidBidderAccount)?;
+        require_keys_eq!(winner_nft_token.owner, auction.highest_bidder, MarketplaceError::InvalidBidderAccount);
+
+        let source = PaymentSource::token(&ctx.accounts.token_program, &ctx.accounts.bid_escrow, &auction_info, signer_seeds);
+        let split = pay_sale(
+            &source,
+            &ctx.accounts.config,
+            &ctx.accounts.fee_treasury.to_account_info(),
+            &ctx.accounts.seller_payment_token.to_account_info(),
+            auction.seller,
+            auction.highest_bid,
+            None,
+            &[],
+        )?;
+        record_sale(ctx.accounts.price_history.as_mut(), auction.nft_mint, auction.seller, auction.highest_bidder, auction.highest_bid, ctx.accounts.bid_escrow.mint, &split)?;
+        winner_nft_token
+    } else {
+        if auction.has_bid() {
+            let refund = ctx.accounts.bidder_refund_token.as_ref().ok_or(MarketplaceError::InvalidBidderAccount)?;
+            require_keys_eq!(refund.owner, auction.highest_bidder, MarketplaceError::InvalidBidderAccount);
+            transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.bid_escrow, refund, &auction_info, auction.highest_bid, signer_seeds)?;
+        }
+        emit_auction_cancelled(auction, true)?;
+        &ctx.accounts.seller_nft_token
+    };
+
+    transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.nft_escrow, nft_destination, &auction_info, 1, signer_seeds)?;
+    close_escrow(&ctx.accounts.token_program, &ctx.accounts.nft_escrow, &ctx.accounts.seller, &auction_info, signer_seeds)?;
+    close_escrow(&ctx.accounts.token_program, &ctx.accounts.bid_escrow, &ctx.accounts.seller, &auction_info, signer_seeds)
+}
+
+pub fn buy_dutch_auction(ctx: Context<BuyDutchAuction>) -> Result<()> {
+    let auction = &ctx.accounts.auction;
+    require!(auction.kind == AuctionKind::Dutch, MarketplaceError::WrongAuctionKind);
+    let price = auction.dutch_price(Clock::get()?.unix_timestamp);
+
+    let buyer = ctx.accounts.buyer.to_account_info();
+    let source = PaymentSource::token(&ctx.accounts.token_program, &ctx.accounts.buyer_payment_token, &buyer, &[]);
+    let split = pay_sale(
+        &source,
+        &ctx.accounts.config,
+        &ctx.accounts.fee_treasury.to_account_info(),
+        &ctx.accounts.seller_payment_token.to_account_info(),
+        auction.seller,
+        price,
+        None,
+        &[],
+    )?;
+    record_sale(ctx.accounts.price_history.as_mut(), auction.nft_mint, auction.seller, ctx.accounts.buyer.key(), price, ctx.accounts.bid_escrow.mint, &split)?;
+
+    let bump = [auction.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"auction", auction.nft_mint.as_ref(), &bump]];
+    let auction_info = auction.to_account_info();
+    transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.nft_escrow, &ctx.accounts.buyer_nft_token, &auction_info, 1, signer_seeds)?;
+    close_escrow(&ctx.accounts.token_program, &ctx.accounts.nft_escrow, &ctx.accounts.seller, &auction_info, signer_seeds)?;
+    close_escrow(&ctx.accounts.token_program, &ctx.accounts.bid_escrow, &ctx.accounts.seller, &auction_info, signer_seeds)
+}
+
+pub fn cancel_auction(ctx: Context<CancelAuction>) -> Result<()> {
+    let auction = &ctx.accounts.auction;
+    require!(!auction.has_bid(), MarketplaceError::AuctionHasBids);
+
+    let bump = [auction.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"auction", auction.nft_mint.as_ref(), &bump]];
+    let auction_info = auction.to_account_info();
+    transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.nft_escrow, &ctx.accounts.seller_nft_token, &auction_info, 1, signer_seeds)?;
+    close_escrow(&ctx.accounts.token_program, &ctx.accounts.nft_escrow, &ctx.accounts.seller, &auction_info, signer_seeds)?;
+    close_escrow(&ctx.accounts.token_program, &ctx.accounts.bid_escrow, &ctx.accounts.seller, &auction_info, signer_seeds)?;
+    emit_auction_cancelled(auction, false)
+}
+
+pub fn make_offer(ctx: Context<MakeOffer>, amount: u64) -> Result<()> {
+    require!(amount > 0, MarketplaceError::InvalidOfferAmount);
+    require!(ctx.accounts.config.accepts(&ctx.accounts.payment_mint.key()), MarketplaceError::UnsupportedPaymentMint);
+
+    let offer = &mut ctx.accounts.offer;
+    offer.nft_mint = ctx.accounts.nft_mint.key();
+    offer.buyer = ctx.accounts.buyer.key();
+    offer.amount = amount;
+    offer.bump = ctx.bumps.offer;
+
+    transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.buyer_payment_token, &ctx.accounts.offer_escrow, &ctx.accounts.buyer.to_account_info(), amount, &[])
+}
+
+pub fn cancel_offer(ctx: Context<CancelOffer>) -> Result<()> {
+    let offer = &ctx.accounts.offer;
+    let bump = [offer.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"offer", offer.nft_mint.as_ref(), offer.buyer.as_ref(), &bump]];
+    let offer_info = offer.to_account_info();
+
+    transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.offer_escrow, &ctx.accounts.buyer_payment_token, &offer_info, offer.amount, signer_seeds)?;
+    close_escrow(&ctx.accounts.token_program, &ctx.accounts.offer_escrow, &ctx.accounts.buyer, &offer_info, signer_seeds)
+}
+
+// The current holder hands over the NFT and is paid from the offer escrow
+pub fn accept_offer(ctx: Context<AcceptOffer>) -> Result<()> {
+    let offer = &ctx.accounts.offer;
+    let bump = [offer.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"offer", offer.nft_mint.as_ref(), offer.buyer.as_ref(), &bump]];
+    let offer_info = offer.to_account_info();
+
+    transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.seller_nft_token, &ctx.accounts.buyer_nft_token, &ctx.accounts.seller.to_account_info(), 1, &[])?;
+    let source = PaymentSource::token(&ctx.accounts.token_program, &ctx.accounts.offer_escrow, &offer_info, signer_seeds);
+    let split = pay_sale(
+        &source,
+        &ctx.accounts.config,
+        &ctx.accounts.fee_treasury.to_account_info(),
+        &ctx.accounts.seller_payment_token.to_account_info(),
+        ctx.accounts.seller.key(),
+        offer.amount,
+        None,
+        &[],
+    )?;
+    record_sale(ctx.accounts.price_history.as_mut(), offer.nft_mint, ctx.accounts.seller.key(), offer.buyer, offer.amount, ctx.accounts.offer_escrow.mint, &split)?;
+    close_escrow(&ctx.accounts.token_program, &ctx.accounts.offer_escrow, &ctx.accounts.buyer, &offer_info, signer_seeds)
+}
+
+pub fn make_core_offer(ctx: Context<MakeCoreOffer>, amount: u64) -> Result<()> {
+    require!(amount > 0, MarketplaceError::InvalidOfferAmount);
+    require!(ctx.accounts.config.accepts(&ctx.accounts.payment_mint.key()), MarketplaceError::UnsupportedPaymentMint);
+
+    let offer = &mut ctx.accounts.offer;
+    offer.nft_mint = ctx.accounts.asset.key();
+    offer.buyer = ctx.accounts.buyer.key();
+    offer.amount = amount;
+    offer.bump = ctx.bumps.offer;
+
+    transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.buyer_payment_token, &ctx.accounts.offer_escrow, &ctx.accounts.buyer.to_account_info(), amount, &[])
+}
+
+// The asset's owner transfers it to the buyer and is paid from the offer escrow, less royalties.
+// Remaining accounts: royalty creator token accounts
+pub fn accept_core_offer<'info>(ctx: Context<'_, '_, 'info, 'info, AcceptCoreOffer<'info>>) -> Result<()> {
+    let game_item = &ctx.accounts.game_item;
+    require!(!game_item.is_equipped && !game_item.is_listed, MarketplaceError::ItemUnavailable);
+
+    let asset = BaseAssetV1::try_from(&ctx.accounts.asset.to_account_info())
+        .map_err(|_| error!(MarketplaceError::InvalidAsset))?;
+    require_keys_eq!(asset.owner, ctx.accounts.seller.key(), MarketplaceError::NotAssetOwner);
+
+    let collection = ctx.accounts.collection.as_ref().map(|c| c.to_account_info());
+    let royalties = core_royalties(&ctx.accounts.asset.to_account_info(), collection.as_ref())?;
+
+    let offer = &ctx.accounts.offer;
+    let bump = [offer.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"offer", offer.nft_mint.as_ref(), offer.buyer.as_ref(), &bump]];
+    let offer_info = offer.to_account_info();
+    let source = PaymentSource::token(&ctx.accounts.token_program, &ctx.accounts.offer_escrow, &offer_info, signer_seeds);
+    let split = pay_sale(
+        &source,
+        &ctx.accounts.config,
+        &ctx.accounts.fee_treasury.to_account_info(),
+        &ctx.accounts.seller_payment_token.to_account_info(),
+        ctx.accounts.seller.key(),
+        offer.amount,
+        royalties.as_ref(),
+        ctx.remaining_accounts,
+    )?;
+    record_sale(ctx.accounts.price_history.as_mut(), offer.nft_mint, ctx.accounts.seller.key(), offer.buyer, offer.amount, ctx.accounts.offer_escrow.mint, &split)?;
+
+    TransferV1CpiBuilder::new(&ctx.accounts.mpl_core_program.to_account_info())
+        .asset(&ctx.accounts.asset.to_account_info())
+        .collection(collection.as_ref())
+        .payer(&ctx.accounts.seller.to_account_info())
+        .authority(Some(&ctx.accounts.seller.to_account_info()))
+        .new_owner(&ctx.accounts.buyer.to_account_info())
+        .system_program(Some(&ctx.accounts.system_program.to_account_info()))
+        .invoke()?;
+
+    // The asset's listing PDA is the authority nft-items trusts to refresh GameItem.owner
+    let asset_key = ctx.accounts.asset.key();
+    let listing_bump = [ctx.bumps.listing_authority];
+    let listing_seeds: &[&[&[u8]]] = &[&[b"listing", asset_key.as_ref(), &listing_bump]];
+    sync_game_item(&ctx.accounts.nft_items_program, &ctx.accounts.game_item.to_account_info(), &ctx.accounts.asset, &ctx.accounts.listing_authority, listing_seeds, false)?;
+
+    close_escrow(&ctx.accounts.token_program, &ctx.accounts.offer_escrow, &ctx.accounts.buyer, &offer_info, signer_seeds)
+}
+
+pub fn make_collection_bid(
+    ctx: Context<MakeCollectionBid>,
+    bid_id: u64,
+    price: u64,
+    quantity: u32,
+    item_type: Option<ItemType>,
+    min_rarity: u8,
+) -> Result<()> {
+    require!(price > 0 && quantity > 0, MarketplaceError::InvalidOfferAmount);
+    require!(ctx.accounts.config.accepts(&ctx.accounts.payment_mint.key()), MarketplaceError::UnsupportedPaymentMint);
+
+    let bid = &mut ctx.accounts.bid;
+    bid.buyer = ctx.accounts.buyer.key();
+    bid.collection = ctx.accounts.collection.key();
+    bid.bid_id = bid_id;
+    bid.price = price;
+    bid.quantity = quantity;
+    bid.item_type = item_type;
+    bid.min_rarity = min_rarity;
+    bid.bump = ctx.bumps.bid;
+
+    let total = price.checked_mul(quantity as u64).ok_or(MarketplaceError::MathOverflow)?;
+    transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.buyer_payment_token, &ctx.accounts.bid_escrow, &ctx.accounts.buyer.to_account_info(), total, &[])
+}
+
+pub fn cancel_collection_bid(ctx: Context<CancelCollectionBid>) -> Result<()> {
+    let bid = &ctx.accounts.bid;
+    let bump = [bid.bump];
+    let bid_id = bid.bid_id.to_le_bytes();
+    let signer_seeds: &[&[&[u8]]] = &[&[b"collection_bid", bid.buyer.as_ref(), &bid_id, &bump]];
+    let bid_info = bid.to_account_info();
+
+    let remaining = ctx.accounts.bid_escrow.amount;
+    transfer_tokens(&ctx.accounts.token_program, &ctx.accounts.bid_escrow, &ctx.accounts.buyer_payment_token, &bid_info, remaining, signer_seeds)?;
+    close_escrow(&ctx.accounts.token_program, &ctx.accounts.bid_escrow, &ctx.accounts.buyer, &bid_info, signer_seeds)
+}
+
+// Any holder of a matching item can sell into the bid. Remaining accounts: royalty creator token accounts
+pub fn fill_collection_bid<'info>(ctx: Context<'_, '_, 'info, 'info, FillCollectionBid<'info>>) -> Result<()> {
+    let bid = &ctx.accounts.bid;
+    let game_item = &ctx.accounts.game_item;
+    require!(bid.item_type.map_or(true, |t| t == game_item.item_type), MarketplaceError::ItemDoesNotMatchBid);
+    require!(game_item.rarity >= bid.min_rarity, MarketplaceError::ItemDoesNotMatchBid);
+    require!(!game_item.is_equipped && !game_item.is_listed, MarketplaceError::ItemUnavailable);
+
+    let asset = BaseAssetV1::try_from(&ctx.accounts.asset.to_account_info())
+        .map_err(|_| error!(MarketplaceError::InvalidAsset))?;
+    require_keys_eq!(asset.owner, ctx.accounts.seller.key(), MarketplaceError::NotAssetOwner);
+    require!(asset.update_authority == UpdateAuthority::Collection(bid.collection), MarketplaceError::ItemDoesNotMatchBid);
+
+    let collection = ctx.accounts.collection.to_account_info();
+    let royalties = core_royalties(&ctx.accounts.asset.to_account_info(), Some(&collection))?;
+
+    let bump = [bid.bump];
+    let bid_id = bid.bid_id.to_le_bytes();
+    let buyer_key = bid.buyer;
+    let signer_seeds: &[&[&[u8]]] = &[&[b"collection_bid", buyer_key.as_ref(), &bid_id, &bump]];
+    let bid_info = bid.to_account_info();
+    let source = PaymentSource::token(&ctx.accounts.token_program, &ctx.accounts.bid_escrow, &bid_info, signer_seeds);
+    let split = pay_sale(
+        &source,
+        &ctx.accounts.config,
+        &ctx.accounts.fee_treasury.to_account_info(),
+        &ctx.accounts.seller_payment_token.to_account_info(),
+        ctx.accounts.seller.key(),
+        bid.price,
+        royalties.as_ref(),
+        ctx.remaining_accounts,
+    )?;
+    record_sale(ctx.accounts.price_history.as_mut(), ctx.accounts.asset.key(), ctx.accounts.seller.key(), buyer_key, bid.price, ctx.accounts.bid_escrow.mint, &split)?;
+
+    TransferV1CpiBuilder::new(&ctx.accounts.mpl_core_program.to_account_info())
+        .asset(&ctx.accounts.asset.to_account_info())
+        .collection(Some(&collection))
+        .payer(&ctx.accounts.seller.to_account_info())
+        .authority(Some(&ctx.accounts.seller.to_account_info()))
+        .new_owner(&ctx.accounts.buyer.to_account_info())
+        .system_program(Some(&ctx.accounts.system_program.to_account_info()))
+        .invoke()?;
+
+    // The asset's listing PDA is the authority nft-items trusts to refresh GameItem.owner
+    let asset_key = ctx.accounts.asset.key();
+    let listing_bump = [ctx.bumps.listing_authority];
+    let listing_seeds: &[&[&[u8]]] = &[&[b"listing", asset_key.as_ref(), &listing_bump]];
+    sync_game_item(&ctx.accounts.nft_items_program, &ctx.accounts.game_item.to_account_info(), &ctx.accounts.asset, &ctx.accounts.listing_authority, listing_seeds, false)?;
+
+    let bid = &mut ctx.accounts.bid;
+    bid.quantity -= 1;
+    if bid.quantity == 0 {
+        close_escrow(&ctx.accounts.token_program, &ctx.accounts.bid_escrow, &ctx.accounts.buyer, &bid_info, signer_seeds)?;
+        bid.close(ctx.accounts.buyer.to_account_info())?;
+    }
+    Ok(())
+}
+
+// Anyone can open the history for a mint and payment mint, sales record into it from then on
+pub fn initialize_price_history(ctx: Context<InitializePriceHistory>) -> Result<()> {
+    let history = &mut ctx.accounts.price_history;
+    history.mint = ctx.accounts.mint.key();
+    history.payment_mint = ctx.accounts.payment_mint.key();
+    history.bump = ctx.bumps.price_history;
+    Ok(())
+}
+
+// Every item carries its own price so each one is paid out with its own royalties.
+// Remaining accounts per item, in `items` order: SPL [mint, seller token account, bundle escrow ATA],
+// Core [asset, collection or the Core program id when it has none, game item, listing PDA of the asset]
+pub fn list_bundle<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ListBundle<'info>>,
+    bundle_id: u64,
+    payment_mint: Pubkey,
+    items: Vec<BundleItem>,
+) -> Result<()> {
+    require!(!items.is_empty() && items.len() <= BundleListing::MAX_ITEMS, MarketplaceError::InvalidBundleSize);
+    require!(ctx.accounts.config.accepts(&payment_mint), MarketplaceError::UnsupportedPaymentMint);
+
+    let bundle_info = ctx.accounts.bundle.to_account_info();
+    let seller = &ctx.accounts.seller;
+    let mut price = 0u64;
+    let mut accounts = ctx.remaining_accounts;
+    for item in items.iter() {
+        price = price.checked_add(item.price).ok_or(MarketplaceError::MathOverflow)?;
+        let mint = match item.standard {
+            AssetStandard::SplToken => {
+                let [mint, seller_token, escrow] = take_bundle_accounts(&mut accounts)?;
+                require!(Account::<Mint>::try_from(mint)?.decimals == 0, MarketplaceError::InvalidNftMint);
+                require_keys_eq!(escrow.key(), get_associated_token_address(bundle_info.key, mint.key), MarketplaceError::BundleItemMismatch);
+
+                let create_accounts = associated_token::Create {
+                    payer: seller.to_account_info(),
+                    associated_token: escrow.clone(),
+                    authority: bundle_info.clone(),
+                    mint: mint.clone(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                };
+                associated_token::create_idempotent(CpiContext::new(ctx.accounts.associated_token_program.to_account_info(), create_accounts))?;
+
+                let seller_token = Account::<TokenAccount>::try_from(seller_token)?;
+                let escrow = Account::<TokenAccount>::try_from(escrow)?;
+                transfer_tokens(&ctx.accounts.token_program, &seller_token, &escrow, &seller.to_account_info(), 1, &[])?;
+                mint.key()
+            }
+            AssetStandard::Core => {
+                let [asset, collection, game_item, listing_authority] = take_bundle_accounts(&mut accounts)?;
+                require_keys_eq!(*asset.owner, MPL_CORE_ID, MarketplaceError::InvalidAsset);
+                let base = BaseAssetV1::try_from(asset).map_err(|_| error!(MarketplaceError::InvalidAsset))?;
+                require_keys_eq!(base.owner, seller.key(), MarketplaceError::NotAssetOwner);
+
+                let collection = (collection.key() != MPL_CORE_ID).then_some(collection);
+                let plugins = [
+                    Plugin::FreezeDelegate(FreezeDelegate { frozen: true }),
+                    Plugin::TransferDelegate(TransferDelegate {}),
+                ];
+                for plugin in plugins {
+                    delegate_core_plugin(&ctx.accounts.mpl_core_program, asset, collection, seller, &ctx.accounts.system_program, plugin, bundle_info.key())?;
+                }
+                sync_bundled_game_item(&ctx.accounts.nft_items_program, game_item, asset, listing_authority, true)?;
+                asset.key()
+            }
+        };
+        require_keys_eq!(mint, item.mint, MarketplaceError::BundleItemMismatch);
+    }
+    require!(accounts.is_empty(), MarketplaceError::BundleItemMismatch);
+
+    let bundle = &mut ctx.accounts.bundle;
+    bundle.seller = seller.key();
+    bundle.bundle_id = bundle_id;
+    bundle.price = price;
+    bundle.payment_mint = payment_mint;
+    bundle.items = items;
+    bundle.bump = ctx.bumps.bundle;
+
+    // Indexers see each bundled item as listed under the bundle account
+    let slot = Clock::get()?.slot;
+    for item in bundle.items.iter() {
+        emit!(ListingCreated {
+            listing: bundle.key(),
+            mint: item.mint,
+            seller: bundle.seller,
+            price: item.price,
+            payment_mint: bundle.payment_mint,
+            expires_at: None,
+            slot,
+        });
+    }
+    Ok(())
+}
+
+// Remaining accounts per item, in bundle order: SPL [bundle escrow ATA, buyer token account, price history],
+// Core [asset, collection or the Core program id, game item, listing PDA of the asset, price history,
+// then one payment account per royalty creator of that asset]. The price history is the item's PriceHistory
+// for the bundle's payment mint, or the marketplace program id to skip it. Each item is paid out and
+// recorded as its own sale
+pub fn buy_bundle<'info>(ctx: Context<'_, '_, 'info, 'info, BuyBundle<'info>>) -> Result<()> {
+    let bundle = &ctx.accounts.bundle;
+    require!(ctx.accounts.config.accepts(&bundle.payment_mint), MarketplaceError::UnsupportedPaymentMint);
+
+    let source = PaymentSource::buyer(
+        bundle.payment_mint,
+        &ctx.accounts.buyer,
+        ctx.accounts.buyer_payment_token.as_ref(),
+        &ctx.accounts.token_program,
+        &ctx.accounts.system_program,
+    )?;
+    let pay_item = |price: u64, royalties: Option<&Royalties>, royalty_accounts: &[AccountInfo<'info>]| {
+        pay_sale(
+            &source,
+            &ctx.accounts.config,
+            &ctx.accounts.fee_treasury,
+            &ctx.accounts.seller_payment,
+            bundle.seller,
+            price,
+            royalties,
+            royalty_accounts,
+        )
+    };
+
+    let bump = [bundle.bump];
+    let bundle_id = bundle.bundle_id.to_le_bytes();
+    let signer_seeds: &[&[&[u8]]] = &[&[b"bundle", bundle.seller.as_ref(), &bundle_id, &bump]];
+    let bundle_info = bundle.to_account_info();
+    let buyer = &ctx.accounts.buyer;
+
+    let mut accounts = ctx.remaining_accounts;
+    for item in bundle.items.iter() {
+        match item.standard {
+            AssetStandard::SplToken => {
+                let [escrow, buyer_token, price_history] = take_bundle_accounts(&mut accounts)?;
+                require_keys_eq!(escrow.key(), get_associated_token_address(bundle_info.key, &item.mint), MarketplaceError::BundleItemMismatch);
+                let escrow = Account::<TokenAccount>::try_from(escrow)?;
+                let buyer_token = Account::<TokenAccount>::try_from(buyer_token)?;
+                require_keys_eq!(buyer_token.owner, buyer.key(), MarketplaceError::BundleItemMismatch);
+
+                // SPL mints carry no Core royalty plugin, so only the protocol fee is taken
+                let split = pay_item(item.price, None, &[])?;
+                record_bundled_sale(price_history, bundle, item, buyer.key(), &split)?;
+                transfer_tokens(&ctx.accounts.token_program, &escrow, &buyer_token, &bundle_info, 1, signer_seeds)?;
+                close_escrow(&ctx.accounts.token_program, &escrow, &ctx.accounts.seller, &bundle_info, signer_seeds)?;
+            }
+            AssetStandard::Core => {
+                let [asset, collection, game_item, listing_authority, price_history] = take_bundle_accounts(&mut accounts)?;
+                require_keys_eq!(asset.key(), item.mint, MarketplaceError::BundleItemMismatch);
+                let collection = (collection.key() != MPL_CORE_ID).then_some(collection);
+                let royalties = core_royalties(asset, collection)?;
+                let royalty_accounts = take_accounts(&mut accounts, royalties.as_ref().map_or(0, |r| r.creators.len()))?;
+                let split = pay_item(item.price, royalties.as_ref(), royalty_accounts)?;
+                record_bundled_sale(price_history, bundle, item, buyer.key(), &split)?;
+
+                thaw_core_asset(&ctx.accounts.mpl_core_program, asset, collection, buyer, &bundle_info, &ctx.accounts.system_program, signer_seeds)?;
+                TransferV1CpiBuilder::new(&ctx.accounts.mpl_core_program.to_account_info())
+                    .asset(asset)
+                    .collection(collection)
+                    .payer(&buyer.to_account_info())
+                    .authority(Some(&bundle_info))
+                    .new_owner(&buyer.to_account_info())
+                    .system_program(Some(&ctx.accounts.system_program.to_account_info()))
+                    .invoke_signed(signer_seeds)?;
+                sync_bundled_game_item(&ctx.accounts.nft_items_program, game_item, asset, listing_authority, false)?;
+            }
+        }
+    }
+    require!(accounts.is_empty(), MarketplaceError::BundleItemMismatch);
+    Ok(())
+}
+
+// Remaining accounts per item, in bundle order: SPL [bundle escrow ATA, seller token account],
+// Core [asset, collection or the Core program id, game item, listing PDA of the asset]
+pub fn cancel_bundle<'info>(ctx: Context<'_, '_, 'info, 'info, CancelBundle<'info>>) -> Result<()> {
+    let bundle = &ctx.accounts.bundle;
+    let bump = [bundle.bump];
+    let bundle_id = bundle.bundle_id.to_le_bytes();
+    let signer_seeds: &[&[&[u8]]] = &[&[b"bundle", bundle.seller.as_ref(), &bundle_id, &bump]];
+    let bundle_info = bundle.to_account_info();
+    let seller = &ctx.accounts.seller;
+
+    let mut accounts = ctx.remaining_accounts;
+    for item in bundle.items.iter() {
+        match item.standard {
+            AssetStandard::SplToken => {
+                let [escrow, seller_token] = take_bundle_accounts(&mut accounts)?;
+                require_keys_eq!(escrow.key(), get_associated_token_address(bundle_info.key, &item.mint), MarketplaceError::BundleItemMismatch);
+                let escrow = Account::<TokenAccount>::try_from(escrow)?;
+                let seller_token = Account::<TokenAccount>::try_from(seller_token)?;
+
+                transfer_tokens(&ctx.accounts.token_program, &escrow, &seller_token, &bundle_info, 1, signer_seeds)?;
+                close_escrow(&ctx.accounts.token_program, &escrow, seller, &bundle_info, signer_seeds)?;
+            }
+            AssetStandard::Core => {
+                let [asset, collection, game_item, listing_authority] = take_bundle_accounts(&mut accounts)?;
+                require_keys_eq!(asset.key(), item.mint, MarketplaceError::BundleItemMismatch);
+                let collection = (collection.key() != MPL_CORE_ID).then_some(collection);
+
+                release_core_asset(&ctx.accounts.mpl_core_program, asset, collection, seller, &bundle_info, &ctx.accounts.system_program, signer_seeds)?;
+                sync_bundled_game_item(&ctx.accounts.nft_items_program, game_item, asset, listing_authority, false)?;
+            }
+        }
+        emit!(ListingCancelled {
+            listing: bundle_info.key(),
+            mint: item.mint,
+            seller: bundle.seller,
+            expired: false,
+            slot: Clock::get()?.slot,
+        });
+    }
+    require!(accounts.is_empty(), MarketplaceError::BundleItemMismatch);
+    Ok(())
+}
+
+pub struct SaleSplit {
+    pub fee: u64,
+    pub royalty: u64,
+    pub seller_amount: u64,
+}
+
+pub fn split_sale(price: u64, fee_bps: u16, royalty_bps: u16) -> Result<SaleSplit> {
+    let total_bps = (fee_bps as u64).checked_add(royalty_bps as u64).ok_or(MarketplaceError::MathOverflow)?;
+    require!(total_bps <= 10_000, MarketplaceError::FeesExceedPrice);
+
+    let bps_of = |bps: u16| -> Result<u64> {
+        let amount = (price as u128)
+            .checked_mul(bps as u128)
+            .ok_or(MarketplaceError::MathOverflow)?
+            / 10_000;
+        Ok(amount as u64)
+    };
+    let fee = bps_of(fee_bps)?;
+    let royalty = bps_of(royalty_bps)?;
+    let seller_amount = price
+        .checked_sub(fee)
+        .and_then(|rest| rest.checked_sub(royalty))
+        .ok_or(MarketplaceError::MathOverflow)?;
+    Ok(SaleSplit { fee, royalty, seller_amount })
+}
+
+// Royalties live on the asset, falling back to its collection
+fn core_royalties(asset: &AccountInfo, collection: Option<&AccountInfo>) -> Result<Option<Royalties>> {
+    let base = BaseAssetV1::try_from(asset).map_err(|_| error!(MarketplaceError::InvalidAsset))?;
+    if let UpdateAuthority::Collection(collection_key) = base.update_authority {
+        let collection = collection.ok_or(MarketplaceError::CollectionMismatch)?;
+        require_keys_eq!(collection.key(), collection_key, MarketplaceError::CollectionMismatch);
+    }
+
+    if let Ok((_, royalties, _)) = fetch_plugin::<BaseAssetV1, Royalties>(asset, PluginType::Royalties) {
+        return Ok(Some(royalties));
+    }
+    Ok(collection.and_then(|c| {
+        fetch_plugin::<BaseCollectionV1, Royalties>(c, PluginType::Royalties)
+            .ok()
+            .map(|(_, royalties, _)| royalties)
+    }))
+}
+
+// Where sale proceeds are drawn from: a wallet paying lamports, or a token account owned by the buyer or an escrow PDA
+struct PaymentSource<'a, 'info> {
+    program: AccountInfo<'info>,
+    from: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    mint: Pubkey,
+    signer_seeds: &'a [&'a [&'a [u8]]],
+}
+
+impl<'a, 'info> PaymentSource<'a, 'info> {
+    fn token(
+        token_program: &Program<'info, Token>,
+        from: &Account<'info, TokenAccount>,
+        authority: &AccountInfo<'info>,
+        signer_seeds: &'a [&'a [&'a [u8]]],
+    ) -> Self {
+        Self {
+            program: token_program.to_account_info(),
+            from: from.to_account_info(),
+            authority: authority.clone(),
+            mint: from.mint,
+            signer_seeds,
+        }
+    }
+
+    // Native SOL comes straight from the buyer's wallet, any other mint from their token account
+    fn buyer(
+        payment_mint: Pubkey,
+        buyer: &Signer<'info>,
+        buyer_payment_token: Option<&Account<'info, TokenAccount>>,
+        token_program: &Program<'info, Token>,
+        system_program: &Program<'info, System>,
+    ) -> Result<Self> {
+        if payment_mint == NATIVE_SOL {
+            return Ok(Self {
+                program: system_program.to_account_info(),
+                from: buyer.to_account_info(),
+                authority: buyer.to_account_info(),
+                mint: NATIVE_SOL,
+                signer_seeds: &[],
+            });
+        }
+        let buyer_token = buyer_payment_token.ok_or(MarketplaceError::InvalidPaymentAccount)?;
+        require_keys_eq!(buyer_token.mint, payment_mint, MarketplaceError::InvalidPaymentAccount);
+        Ok(Self::token(token_program, buyer_token, &buyer.to_account_info(), &[]))
+    }
+
+    // `to` must be the recipient wallet for SOL, otherwise a token account of the payment mint owned by the recipient
+    fn pay(&self, to: &AccountInfo<'info>, recipient: Pubkey, amount: u64, error: MarketplaceError) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        if self.mint == NATIVE_SOL {
+            require_keys_eq!(to.key(), recipient, error);
+            let cpi_accounts = system_program::Transfer {
+                from: self.from.clone(),
+                to: to.clone(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(self.program.clone(), cpi_accounts, self.signer_seeds);
+            return system_program::transfer(cpi_ctx, amount);
+        }
+
+        require_keys_eq!(*to.owner, token::ID, error);
+        let destination = TokenAccount::try_deserialize(&mut &to.try_borrow_data()?[..])?;
+        require_keys_eq!(destination.owner, recipient, error);
+        require_keys_eq!(destination.mint, self.mint, error);
+
+        let cpi_accounts = Transfer {
+            from: self.from.clone(),
+            to: to.clone(),
+            authority: self.authority.clone(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.program.clone(), cpi_accounts, self.signer_seeds);
+        token::transfer(cpi_ctx, amount)
+    }
+}
+
+// Splits the price between the fee treasury, royalty creators and the seller.
+// Rounding dust from the per-creator split stays with the seller.
+#[allow(clippy::too_many_arguments)]
+fn pay_sale<'info>(
+    source: &PaymentSource<'_, 'info>,
+    config: &MarketplaceConfig,
+    fee_treasury: &AccountInfo<'info>,
+    seller_payment: &AccountInfo<'info>,
+    seller: Pubkey,
+    price: u64,
+    royalties: Option<&Royalties>,
+    royalty_accounts: &[AccountInfo<'info>],
+) -> Result<SaleSplit> {
+    let royalty_bps = royalties.map_or(0, |r| r.basis_points);
+    let split = split_sale(price, config.fee_bps, royalty_bps)?;
+
+    let mut royalty_paid = 0u64;
+    if let Some(royalties) = royalties {
+        require!(royalty_accounts.len() == royalties.creators.len(), MarketplaceError::InvalidRoyaltyRecipient);
+        for (creator, account) in royalties.creators.iter().zip(royalty_accounts.iter()) {
+            let share = split.royalty
+                .checked_mul(creator.percentage as u64)
+                .ok_or(MarketplaceError::MathOverflow)?
+                / 100;
+            source.pay(account, creator.address, share, MarketplaceError::InvalidRoyaltyRecipient)?;
+            royalty_paid = royalty_paid.checked_add(share).ok_or(MarketplaceError::MathOverflow)?;
+        }
+    }
+
+    let seller_amount = split.seller_amount
+        .checked_add(split.royalty - royalty_paid)
+        .ok_or(MarketplaceError::MathOverflow)?;
+    source.pay(fee_treasury, config.fee_treasury, split.fee, MarketplaceError::InvalidFeeTreasury)?;
+    source.pay(seller_payment, seller, seller_amount, MarketplaceError::InvalidPaymentAccount)?;
+    Ok(SaleSplit { fee: split.fee, royalty: royalty_paid, seller_amount })
+}
+
+fn transfer_tokens<'info>(
+    token_program: &Program<'info, Token>,
+    from: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let cpi_accounts = Transfer {
+        from: from.to_account_info(),
+        to: to.to_account_info(),
+        authority: authority.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds);
+    token::transfer(cpi_ctx, amount)
+}
+
+fn close_escrow<'info>(
+    token_program: &Program<'info, Token>,
+    escrow: &Account<'info, TokenAccount>,
+    destination: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let close_accounts = CloseAccount {
+        account: escrow.to_account_info(),
+        destination: destination.clone(),
+        authority: authority.clone(),
+    };
+    let close_ctx = CpiContext::new_with_signer(token_program.to_account_info(), close_accounts, signer_seeds);
+    token::close_account(close_ctx)
+}
+
+// Adds the owner-managed plugin with the listing as authority, or re-delegates it if a past sale left it on the asset
+fn delegate_core_plugin<'info>(
+    mpl_core_program: &UncheckedAccount<'info>,
+    asset: &AccountInfo<'info>,
+    collection: Option<&AccountInfo<'info>>,
+    owner: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    plugin: Plugin,
+    delegate: Pubkey,
+) -> Result<()> {
+    let plugin_type = PluginType::from(&plugin);
+    let existing = list_plugins(&asset.try_borrow_data()?).unwrap_or_default();
+
+    if existing.contains(&plugin_type) {
+        UpdatePluginV1CpiBuilder::new(&mpl_core_program.to_account_info())
+            .asset(asset)
+            .collection(collection)
+            .payer(&owner.to_account_info())
+            .authority(Some(&owner.to_account_info()))
+            .system_program(&system_program.to_account_info())
+            .plugin(plugin)
+            .invoke()?;
+        ApprovePluginAuthorityV1CpiBuilder::new(&mpl_core_program.to_account_info())
+            .asset(asset)
+            .collection(collection)
+            .payer(&owner.to_account_info())
+            .authority(Some(&owner.to_account_info()))
+            .system_program(&system_program.to_account_info())
+            .plugin_type(plugin_type)
+            .new_authority(PluginAuthority::Address { address: delegate })
+            .invoke()?;
+    } else {
+        AddPluginV1CpiBuilder::new(&mpl_core_program.to_account_info())
+            .asset(asset)
+            .collection(collection)
+            .payer(&owner.to_account_info())
+            .authority(Some(&owner.to_account_info()))
+            .system_program(&system_program.to_account_info())
+            .plugin(plugin)
+            .init_authority(PluginAuthority::Address { address: delegate })
+            .invoke()?;
+    }
+    Ok(())
+}
+
+fn thaw_core_asset<'info>(
+    mpl_core_program: &UncheckedAccount<'info>,
+    asset: &AccountInfo<'info>,
+    collection: Option<&AccountInfo<'info>>,
+    payer: &Signer<'info>,
+    listing: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    UpdatePluginV1CpiBuilder::new(&mpl_core_program.to_account_info())
+        .asset(&asset.to_account_info())
+        .collection(collection)
+        .payer(&payer.to_account_info())
+        .authority(Some(listing))
+        .system_program(&system_program.to_account_info())
+        .plugin(Plugin::FreezeDelegate(FreezeDelegate { frozen: false }))
+        .invoke_signed(signer_seeds)?;
+    Ok(())
+}
+
+// Thaws the asset and hands both delegates back to its owner
+fn release_core_asset<'info>(
+    mpl_core_program: &UncheckedAccount<'info>,
+    asset: &AccountInfo<'info>,
+    collection: Option<&AccountInfo<'info>>,
+    payer: &Signer<'info>,
+    listing: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    thaw_core_asset(mpl_core_program, asset, collection, payer, listing, system_program, signer_seeds)?;
+    for plugin_type in [PluginType::FreezeDelegate, PluginType::TransferDelegate] {
+        RevokePluginAuthorityV1CpiBuilder::new(&mpl_core_program.to_account_info())
+            .asset(asset)
+            .collection(collection)
+            .payer(&payer.to_account_info())
+            .authority(Some(listing))
+            .system_program(&system_program.to_account_info())
+            .plugin_type(plugin_type)
+            .invoke_signed(signer_seeds)?;
+    }
+    Ok(())
+}
+
+fn emit_listing_created(listing: &Account<Listing>) -> Result<()> {
+    emit!(ListingCreated {
+        listing: listing.key(),
+        mint: listing.nft_mint,
+        seller: listing.seller,
+        price: listing.price,
+        payment_mint: listing.payment_mint,
+        expires_at: listing.expires_at,
+        slot: Clock::get()?.slot,
+    });
+    Ok(())
+}
 
-   
-    listing.is_active = false;
+fn emit_listing_cancelled(listing: &Account<Listing>, expired: bool) -> Result<()> {
+    emit!(ListingCancelled {
+        listing: listing.key(),
+        mint: listing.nft_mint,
+        seller: listing.seller,
+        expired,
+        slot: Clock::get()?.slot,
+    });
     Ok(())
 }
 
+// An auction that closes without a sale, expired when it ended below the reserve
+fn emit_auction_cancelled(auction: &Account<Auction>, expired: bool) -> Result<()> {
+    emit!(ListingCancelled {
+        listing: auction.key(),
+        mint: auction.nft_mint,
+        seller: auction.seller,
+        expired,
+        slot: Clock::get()?.slot,
+    });
+    Ok(())
+}
+
+// Emits the Sale event and appends the price to the mint's history when it was passed in
+fn record_sale(
+    price_history: Option<&mut Account<PriceHistory>>,
+    mint: Pubkey,
+    seller: Pubkey,
+    buyer: Pubkey,
+    price: u64,
+    payment_mint: Pubkey,
+    split: &SaleSplit,
+) -> Result<()> {
+    if let Some(history) = price_history {
+        history.record(price);
+    }
+    emit!(Sale {
+        mint,
+        seller,
+        buyer,
+        price,
+        payment_mint,
+        protocol_fee: split.fee,
+        royalty: split.royalty,
+        slot: Clock::get()?.slot,
+    });
+    Ok(())
+}
+
+// Records one bundled item as a sale, into its price history unless the program id was passed in its place
+fn record_bundled_sale<'info>(
+    price_history: &'info AccountInfo<'info>,
+    bundle: &BundleListing,
+    item: &BundleItem,
+    buyer: Pubkey,
+    split: &SaleSplit,
+) -> Result<()> {
+    if price_history.key() == crate::ID {
+        return record_sale(None, item.mint, bundle.seller, buyer, item.price, bundle.payment_mint, split);
+    }
+    let mut history = Account::<PriceHistory>::try_from(price_history)?;
+    require!(history.mint == item.mint && history.payment_mint == bundle.payment_mint, MarketplaceError::BundleItemMismatch);
+    record_sale(Some(&mut history), item.mint, bundle.seller, buyer, item.price, bundle.payment_mint, split)?;
+    history.exit(&crate::ID)
+}
+
+// Splits off the accounts of the next bundled item
+fn take_bundle_accounts<'a, 'info, const N: usize>(accounts: &mut &'a [AccountInfo<'info>]) -> Result<&'a [AccountInfo<'info>; N]> {
+    Ok(take_accounts(accounts, N)?.try_into().unwrap())
+}
+
+fn take_accounts<'a, 'info>(accounts: &mut &'a [AccountInfo<'info>], count: usize) -> Result<&'a [AccountInfo<'info>]> {
+    require!(accounts.len() >= count, MarketplaceError::BundleItemMismatch);
+    let (item, rest) = accounts.split_at(count);
+    *accounts = rest;
+    Ok(item)
+}
+
+// Bundled Core items are flagged on their GameItem through the asset's listing PDA, which nft-items trusts
+fn sync_bundled_game_item<'info>(
+    nft_items_program: &Program<'info, Nft>,
+    game_item: &AccountInfo<'info>,
+    asset: &AccountInfo<'info>,
+    listing_authority: &AccountInfo<'info>,
+    is_listed: bool,
+) -> Result<()> {
+    let (expected, bump) = Pubkey::find_program_address(&[b"listing", asset.key.as_ref()], &crate::ID);
+    require_keys_eq!(listing_authority.key(), expected, MarketplaceError::BundleItemMismatch);
+    let bump = [bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"listing", asset.key.as_ref(), &bump]];
+    sync_game_item(nft_items_program, game_item, asset, listing_authority, signer_seeds, is_listed)
+}
+
+// Keeps GameItem.owner and GameItem.is_listed in step with the asset, signed by the listing PDA
+fn sync_game_item<'info>(
+    nft_items_program: &Program<'info, Nft>,
+    game_item: &AccountInfo<'info>,
+    asset: &AccountInfo<'info>,
+    listing: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    is_listed: bool,
+) -> Result<()> {
+    let cpi_accounts = nft_items::cpi::accounts::SyncListingState {
+        game_item: game_item.clone(),
+        asset: asset.clone(),
+        listing: listing.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(nft_items_program.to_account_info(), cpi_accounts, signer_seeds);
+    nft_items::cpi::sync_listing_state(cpi_ctx, is_listed)
+}
+
+
+
+#[derive(Accounts)]
+pub struct InitializeMarketplace<'info> {
+    #[account(init, payer = admin, space = 8 + MarketplaceConfig::INIT_SPACE, seeds = [b"marketplace_config"], bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    // Only the upgrade authority can become marketplace admin, so the config can't be front-run after deploy
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// CHECK: Wallet receiving protocol fees, only its address is stored
+    pub fee_treasury: UncheckedAccount<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::ProgramsMarketplace>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ MarketplaceError::NotMarketplaceAdmin)]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeeConfig<'info> {
+    #[account(mut, seeds = [b"marketplace_config"], bump = config.bump, has_one = admin @ MarketplaceError::NotMarketplaceAdmin)]
+    pub config: Account<'info, MarketplaceConfig>,
+    pub admin: Signer<'info>,
+    /// CHECK: Wallet receiving protocol fees, only its address is stored
+    pub fee_treasury: UncheckedAccount<'info>,
+}
 
+#[derive(Accounts)]
+pub struct SetPaymentMints<'info> {
+    #[account(mut, seeds = [b"marketplace_config"], bump = config.bump, has_one = admin @ MarketplaceError::NotMarketplaceAdmin)]
+    pub config: Account<'info, MarketplaceConfig>,
+    pub admin: Signer<'info>,
+}
 
 #[derive(Accounts)]
 pub struct ListNft<'info> {
     #[account(init, payer = seller, space = 8 + Listing::INIT_SPACE, seeds = [b"listing", nft_mint.key().as_ref()], bump)]
     pub listing: Account<'info, Listing>,
+    #[account(
+        init,
+        payer = seller,
+        seeds = [b"escrow", listing.key().as_ref()],
+        bump,
+        token::mint = nft_mint,
+        token::authority = listing,
+    )]
+    pub escrow_nft_token: Account<'info, TokenAccount>,
     #[account(mut)]
     pub seller: Signer<'info>,
-    /// CHECK: Only used for address and seed
-    pub nft_mint: AccountInfo<'info>,
+    #[account(mut, token::mint = nft_mint, token::authority = seller)]
+    pub seller_nft_token: Account<'info, TokenAccount>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    pub nft_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
@@ -85,29 +1358,616 @@ pub struct UpdateListing<'info> {
 
 #[derive(Accounts)]
 pub struct CancelListing<'info> {
-    #[account(mut, seeds = [b"listing", listing.nft_mint.as_ref()], bump = listing.bump, has_one = seller)]
+    #[account(mut, close = seller, seeds = [b"listing", listing.nft_mint.as_ref()], bump = listing.bump, has_one = seller)]
     pub listing: Account<'info, Listing>,
+    #[account(mut, seeds = [b"escrow", listing.key().as_ref()], bump)]
+    pub escrow_nft_token: Account<'info, TokenAccount>,
+    #[account(mut)]
     pub seller: Signer<'info>,
+    #[account(mut, token::mint = listing.nft_mint, token::authority = seller)]
+    pub seller_nft_token: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
 pub struct BuyNft<'info> {
-    #[account(mut, seeds = [b"listing", listing.nft_mint.as_ref()], bump = listing.bump)]
+    #[account(mut, close = seller, seeds = [b"listing", listing.nft_mint.as_ref()], bump = listing.bump, has_one = seller, has_one = nft_mint)]
+    pub listing: Account<'info, Listing>,
+    #[account(mut, seeds = [b"escrow", listing.key().as_ref()], bump)]
+    pub escrow_nft_token: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    /// Buyer's token account in the listing's payment mint, omitted for native SOL
+    #[account(mut, token::authority = buyer)]
+    pub buyer_payment_token: Option<Account<'info, TokenAccount>>,
+    /// CHECK: Seller's address, validated by the listing account
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    /// CHECK: Seller's token account in the payment mint, or the seller wallet for native SOL. Checked when paid
+    #[account(mut)]
+    pub seller_payment: UncheckedAccount<'info>,
+    #[account(mut, token::mint = nft_mint, token::authority = buyer)]
+    pub buyer_nft_token: Account<'info, TokenAccount>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    /// CHECK: Treasury token account in the payment mint, or the treasury wallet for native SOL. Checked when paid
+    #[account(mut)]
+    pub fee_treasury: UncheckedAccount<'info>,
+    pub nft_mint: Account<'info, Mint>,
+    /// Price history of the mint, the sale is recorded when it is passed
+    #[account(mut, seeds = [b"price_history", listing.nft_mint.as_ref(), listing.payment_mint.as_ref()], bump = price_history.bump)]
+    pub price_history: Option<Account<'info, PriceHistory>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseExpiredListing<'info> {
+    #[account(mut, close = seller, seeds = [b"listing", listing.nft_mint.as_ref()], bump = listing.bump, has_one = seller)]
+    pub listing: Account<'info, Listing>,
+    #[account(mut, seeds = [b"escrow", listing.key().as_ref()], bump)]
+    pub escrow_nft_token: Account<'info, TokenAccount>,
+    /// CHECK: Seller's address, validated by the listing account
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    #[account(mut, token::mint = listing.nft_mint, token::authority = seller)]
+    pub seller_nft_token: Account<'info, TokenAccount>,
+    pub cranker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ListCoreAsset<'info> {
+    #[account(init, payer = seller, space = 8 + Listing::INIT_SPACE, seeds = [b"listing", asset.key().as_ref()], bump)]
+    pub listing: Account<'info, Listing>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    /// CHECK: Core asset, owner is read from its data
+    #[account(mut, owner = MPL_CORE_ID)]
+    pub asset: UncheckedAccount<'info>,
+    /// CHECK: Core collection of the asset, validated by the Core program
+    #[account(mut)]
+    pub collection: Option<UncheckedAccount<'info>>,
+    #[account(mut, seeds = [b"game_item", asset.key().as_ref()], bump, seeds::program = nft_items::ID)]
+    pub game_item: Account<'info, GameItem>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    pub nft_items_program: Program<'info, Nft>,
+    /// CHECK: Metaplex Core
+    #[account(address = MPL_CORE_ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyCoreAsset<'info> {
+    #[account(mut, close = seller, seeds = [b"listing", listing.nft_mint.as_ref()], bump = listing.bump, has_one = seller)]
     pub listing: Account<'info, Listing>,
+    /// CHECK: Core asset held by the listing
+    #[account(mut, address = listing.nft_mint)]
+    pub asset: UncheckedAccount<'info>,
+    /// CHECK: Core collection of the asset, validated by the Core program
+    #[account(mut)]
+    pub collection: Option<UncheckedAccount<'info>>,
+    #[account(mut, seeds = [b"game_item", asset.key().as_ref()], bump, seeds::program = nft_items::ID)]
+    pub game_item: Account<'info, GameItem>,
     #[account(mut)]
     pub buyer: Signer<'info>,
+    /// Buyer's token account in the listing's payment mint, omitted for native SOL
+    #[account(mut, token::authority = buyer)]
+    pub buyer_payment_token: Option<Account<'info, TokenAccount>>,
+    /// CHECK: Seller's address, validated by the listing account
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    /// CHECK: Seller's token account in the payment mint, or the seller wallet for native SOL. Checked when paid
+    #[account(mut)]
+    pub seller_payment: UncheckedAccount<'info>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    /// CHECK: Treasury token account in the payment mint, or the treasury wallet for native SOL. Checked when paid
+    #[account(mut)]
+    pub fee_treasury: UncheckedAccount<'info>,
+    /// Price history of the mint, the sale is recorded when it is passed
+    #[account(mut, seeds = [b"price_history", listing.nft_mint.as_ref(), listing.payment_mint.as_ref()], bump = price_history.bump)]
+    pub price_history: Option<Account<'info, PriceHistory>>,
+    pub token_program: Program<'info, Token>,
+    pub nft_items_program: Program<'info, Nft>,
+    /// CHECK: Metaplex Core
+    #[account(address = MPL_CORE_ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelCoreListing<'info> {
+    #[account(mut, close = seller, seeds = [b"listing", listing.nft_mint.as_ref()], bump = listing.bump, has_one = seller)]
+    pub listing: Account<'info, Listing>,
+    /// CHECK: Core asset held by the listing
+    #[account(mut, address = listing.nft_mint)]
+    pub asset: UncheckedAccount<'info>,
+    /// CHECK: Core collection of the asset, validated by the Core program
+    #[account(mut)]
+    pub collection: Option<UncheckedAccount<'info>>,
+    #[account(mut, seeds = [b"game_item", asset.key().as_ref()], bump, seeds::program = nft_items::ID)]
+    pub game_item: Account<'info, GameItem>,
     #[account(mut)]
-    pub buyer_pirate_token: Account<'info, TokenAccount>,
+    pub seller: Signer<'info>,
+    pub nft_items_program: Program<'info, Nft>,
+    /// CHECK: Metaplex Core
+    #[account(address = MPL_CORE_ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseExpiredCoreListing<'info> {
+    #[account(mut, close = seller, seeds = [b"listing", listing.nft_mint.as_ref()], bump = listing.bump, has_one = seller)]
+    pub listing: Account<'info, Listing>,
+    /// CHECK: Core asset held by the listing
+    #[account(mut, address = listing.nft_mint)]
+    pub asset: UncheckedAccount<'info>,
+    /// CHECK: Core collection of the asset, validated by the Core program
+    #[account(mut)]
+    pub collection: Option<UncheckedAccount<'info>>,
+    #[account(mut, seeds = [b"game_item", asset.key().as_ref()], bump, seeds::program = nft_items::ID)]
+    pub game_item: Account<'info, GameItem>,
     /// CHECK: Seller's address, validated by the listing account
     #[account(mut)]
     pub seller: UncheckedAccount<'info>,
     #[account(mut)]
-    pub seller_pirate_token: Account<'info, TokenAccount>,
+    pub cranker: Signer<'info>,
+    pub nft_items_program: Program<'info, Nft>,
+    /// CHECK: Metaplex Core
+    #[account(address = MPL_CORE_ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateAuction<'info> {
+    #[account(init, payer = seller, space = 8 + Auction::INIT_SPACE, seeds = [b"auction", nft_mint.key().as_ref()], bump)]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init,
+        payer = seller,
+        seeds = [b"auction_escrow", auction.key().as_ref()],
+        bump,
+        token::mint = nft_mint,
+        token::authority = auction,
+    )]
+    pub nft_escrow: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = seller,
+        seeds = [b"bid_escrow", auction.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = auction,
+    )]
+    pub bid_escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    #[account(mut, token::mint = nft_mint, token::authority = seller)]
+    pub seller_nft_token: Account<'info, TokenAccount>,
+    pub nft_mint: Account<'info, Mint>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    pub payment_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(mut, seeds = [b"auction", auction.nft_mint.as_ref()], bump = auction.bump)]
+    pub auction: Account<'info, Auction>,
+    #[account(mut, seeds = [b"bid_escrow", auction.key().as_ref()], bump)]
+    pub bid_escrow: Account<'info, TokenAccount>,
+    pub bidder: Signer<'info>,
+    #[account(mut, token::mint = bid_escrow.mint, token::authority = bidder)]
+    pub bidder_payment_token: Account<'info, TokenAccount>,
+    /// Refund destination of the current highest bidder, required once a bid exists
+    #[account(mut, token::mint = bid_escrow.mint)]
+    pub previous_bidder_token: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    #[account(mut, close = seller, seeds = [b"auction", auction.nft_mint.as_ref()], bump = auction.bump, has_one = seller)]
+    pub auction: Account<'info, Auction>,
+    #[account(mut, seeds = [b"auction_escrow", auction.key().as_ref()], bump)]
+    pub nft_escrow: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"bid_escrow", auction.key().as_ref()], bump)]
+    pub bid_escrow: Account<'info, TokenAccount>,
+    /// CHECK: Seller's address, validated by the auction account
     #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    #[account(mut, token::mint = auction.nft_mint, token::authority = seller)]
     pub seller_nft_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = bid_escrow.mint, token::authority = seller)]
+    pub seller_payment_token: Account<'info, TokenAccount>,
+    /// Winner's NFT account, required when the reserve was met
+    #[account(mut, token::mint = auction.nft_mint)]
+    pub winner_nft_token: Option<Account<'info, TokenAccount>>,
+    /// Highest bidder's payment token account, required when the reserve was not met
+    #[account(mut, token::mint = bid_escrow.mint)]
+    pub bidder_refund_token: Option<Account<'info, TokenAccount>>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    #[account(mut, constraint = fee_treasury.owner == config.fee_treasury @ MarketplaceError::InvalidFeeTreasury)]
+    pub fee_treasury: Account<'info, TokenAccount>,
+    /// Price history of the mint, the sale is recorded when it is passed
+    #[account(mut, seeds = [b"price_history", auction.nft_mint.as_ref(), bid_escrow.mint.as_ref()], bump = price_history.bump)]
+    pub price_history: Option<Account<'info, PriceHistory>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BuyDutchAuction<'info> {
+    #[account(mut, close = seller, seeds = [b"auction", auction.nft_mint.as_ref()], bump = auction.bump, has_one = seller)]
+    pub auction: Account<'info, Auction>,
+    #[account(mut, seeds = [b"auction_escrow", auction.key().as_ref()], bump)]
+    pub nft_escrow: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"bid_escrow", auction.key().as_ref()], bump)]
+    pub bid_escrow: Account<'info, TokenAccount>,
     #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(mut, token::mint = bid_escrow.mint, token::authority = buyer)]
+    pub buyer_payment_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = auction.nft_mint, token::authority = buyer)]
     pub buyer_nft_token: Account<'info, TokenAccount>,
+    /// CHECK: Seller's address, validated by the auction account
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    #[account(mut, token::mint = bid_escrow.mint, token::authority = seller)]
+    pub seller_payment_token: Account<'info, TokenAccount>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    #[account(mut, constraint = fee_treasury.owner == config.fee_treasury @ MarketplaceError::InvalidFeeTreasury)]
+    pub fee_treasury: Account<'info, TokenAccount>,
+    /// Price history of the mint, the sale is recorded when it is passed
+    #[account(mut, seeds = [b"price_history", auction.nft_mint.as_ref(), bid_escrow.mint.as_ref()], bump = price_history.bump)]
+    pub price_history: Option<Account<'info, PriceHistory>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuction<'info> {
+    #[account(mut, close = seller, seeds = [b"auction", auction.nft_mint.as_ref()], bump = auction.bump, has_one = seller)]
+    pub auction: Account<'info, Auction>,
+    #[account(mut, seeds = [b"auction_escrow", auction.key().as_ref()], bump)]
+    pub nft_escrow: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"bid_escrow", auction.key().as_ref()], bump)]
+    pub bid_escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    #[account(mut, token::mint = auction.nft_mint, token::authority = seller)]
+    pub seller_nft_token: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MakeOffer<'info> {
+    #[account(init, payer = buyer, space = 8 + Offer::INIT_SPACE, seeds = [b"offer", nft_mint.key().as_ref(), buyer.key().as_ref()], bump)]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        init,
+        payer = buyer,
+        seeds = [b"offer_escrow", offer.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = offer,
+    )]
+    pub offer_escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(mut, token::mint = payment_mint, token::authority = buyer)]
+    pub buyer_payment_token: Account<'info, TokenAccount>,
     pub nft_mint: Account<'info, Mint>,
-    pub pirate_token_mint: Account<'info, Mint>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    pub payment_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelOffer<'info> {
+    #[account(mut, close = buyer, seeds = [b"offer", offer.nft_mint.as_ref(), buyer.key().as_ref()], bump = offer.bump, has_one = buyer)]
+    pub offer: Account<'info, Offer>,
+    #[account(mut, seeds = [b"offer_escrow", offer.key().as_ref()], bump)]
+    pub offer_escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(mut, token::mint = offer_escrow.mint, token::authority = buyer)]
+    pub buyer_payment_token: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOffer<'info> {
+    #[account(mut, close = buyer, seeds = [b"offer", offer.nft_mint.as_ref(), offer.buyer.as_ref()], bump = offer.bump, has_one = buyer)]
+    pub offer: Account<'info, Offer>,
+    #[account(mut, seeds = [b"offer_escrow", offer.key().as_ref()], bump)]
+    pub offer_escrow: Account<'info, TokenAccount>,
+    /// CHECK: Buyer's address, validated by the offer account
+    #[account(mut)]
+    pub buyer: UncheckedAccount<'info>,
+    #[account(mut, token::mint = offer.nft_mint, token::authority = buyer)]
+    pub buyer_nft_token: Account<'info, TokenAccount>,
+    pub seller: Signer<'info>,
+    #[account(mut, token::mint = offer.nft_mint, token::authority = seller)]
+    pub seller_nft_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = offer_escrow.mint, token::authority = seller)]
+    pub seller_payment_token: Account<'info, TokenAccount>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    #[account(mut, constraint = fee_treasury.owner == config.fee_treasury @ MarketplaceError::InvalidFeeTreasury)]
+    pub fee_treasury: Account<'info, TokenAccount>,
+    /// Price history of the mint, the sale is recorded when it is passed
+    #[account(mut, seeds = [b"price_history", offer.nft_mint.as_ref(), offer_escrow.mint.as_ref()], bump = price_history.bump)]
+    pub price_history: Option<Account<'info, PriceHistory>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MakeCoreOffer<'info> {
+    #[account(init, payer = buyer, space = 8 + Offer::INIT_SPACE, seeds = [b"offer", asset.key().as_ref(), buyer.key().as_ref()], bump)]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        init,
+        payer = buyer,
+        seeds = [b"offer_escrow", offer.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = offer,
+    )]
+    pub offer_escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(mut, token::mint = payment_mint, token::authority = buyer)]
+    pub buyer_payment_token: Account<'info, TokenAccount>,
+    /// CHECK: Core asset the offer is for
+    #[account(owner = MPL_CORE_ID)]
+    pub asset: UncheckedAccount<'info>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    pub payment_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptCoreOffer<'info> {
+    #[account(mut, close = buyer, seeds = [b"offer", offer.nft_mint.as_ref(), offer.buyer.as_ref()], bump = offer.bump, has_one = buyer)]
+    pub offer: Account<'info, Offer>,
+    #[account(mut, seeds = [b"offer_escrow", offer.key().as_ref()], bump)]
+    pub offer_escrow: Account<'info, TokenAccount>,
+    /// CHECK: Buyer's address, validated by the offer account
+    #[account(mut)]
+    pub buyer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    #[account(mut, token::mint = offer_escrow.mint, token::authority = seller)]
+    pub seller_payment_token: Account<'info, TokenAccount>,
+    /// CHECK: Core asset of the offer, owner is read from its data
+    #[account(mut, owner = MPL_CORE_ID, address = offer.nft_mint)]
+    pub asset: UncheckedAccount<'info>,
+    /// CHECK: Core collection of the asset, validated by the Core program
+    #[account(mut)]
+    pub collection: Option<UncheckedAccount<'info>>,
+    #[account(mut, seeds = [b"game_item", asset.key().as_ref()], bump, seeds::program = nft_items::ID)]
+    pub game_item: Account<'info, GameItem>,
+    /// CHECK: Listing PDA of the asset, only used as the signer nft-items expects
+    #[account(seeds = [b"listing", asset.key().as_ref()], bump)]
+    pub listing_authority: UncheckedAccount<'info>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    #[account(mut, constraint = fee_treasury.owner == config.fee_treasury @ MarketplaceError::InvalidFeeTreasury)]
+    pub fee_treasury: Account<'info, TokenAccount>,
+    /// Price history of the asset, the sale is recorded when it is passed
+    #[account(mut, seeds = [b"price_history", asset.key().as_ref(), offer_escrow.mint.as_ref()], bump = price_history.bump)]
+    pub price_history: Option<Account<'info, PriceHistory>>,
+    pub token_program: Program<'info, Token>,
+    pub nft_items_program: Program<'info, Nft>,
+    /// CHECK: Metaplex Core
+    #[account(address = MPL_CORE_ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(bid_id: u64)]
+pub struct MakeCollectionBid<'info> {
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + CollectionBid::INIT_SPACE,
+        seeds = [b"collection_bid", buyer.key().as_ref(), &bid_id.to_le_bytes()],
+        bump
+    )]
+    pub bid: Account<'info, CollectionBid>,
+    #[account(
+        init,
+        payer = buyer,
+        seeds = [b"collection_bid_escrow", bid.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = bid,
+    )]
+    pub bid_escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(mut, token::mint = payment_mint, token::authority = buyer)]
+    pub buyer_payment_token: Account<'info, TokenAccount>,
+    /// CHECK: Core collection the bid applies to
+    #[account(owner = MPL_CORE_ID)]
+    pub collection: UncheckedAccount<'info>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    pub payment_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelCollectionBid<'info> {
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [b"collection_bid", buyer.key().as_ref(), &bid.bid_id.to_le_bytes()],
+        bump = bid.bump,
+        has_one = buyer
+    )]
+    pub bid: Account<'info, CollectionBid>,
+    #[account(mut, seeds = [b"collection_bid_escrow", bid.key().as_ref()], bump)]
+    pub bid_escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(mut, token::mint = bid_escrow.mint, token::authority = buyer)]
+    pub buyer_payment_token: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct FillCollectionBid<'info> {
+    #[account(
+        mut,
+        seeds = [b"collection_bid", bid.buyer.as_ref(), &bid.bid_id.to_le_bytes()],
+        bump = bid.bump,
+        has_one = buyer,
+        has_one = collection
+    )]
+    pub bid: Account<'info, CollectionBid>,
+    #[account(mut, seeds = [b"collection_bid_escrow", bid.key().as_ref()], bump)]
+    pub bid_escrow: Account<'info, TokenAccount>,
+    /// CHECK: Buyer's address, validated by the bid account
+    #[account(mut)]
+    pub buyer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    #[account(mut, token::mint = bid_escrow.mint, token::authority = seller)]
+    pub seller_payment_token: Account<'info, TokenAccount>,
+    /// CHECK: Core asset, owner and collection are read from its data
+    #[account(mut, owner = MPL_CORE_ID)]
+    pub asset: UncheckedAccount<'info>,
+    /// CHECK: Core collection, validated by the bid account
+    #[account(mut)]
+    pub collection: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"game_item", asset.key().as_ref()], bump, seeds::program = nft_items::ID)]
+    pub game_item: Account<'info, GameItem>,
+    /// CHECK: Listing PDA of the asset, only used as the signer nft-items expects
+    #[account(seeds = [b"listing", asset.key().as_ref()], bump)]
+    pub listing_authority: UncheckedAccount<'info>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    #[account(mut, constraint = fee_treasury.owner == config.fee_treasury @ MarketplaceError::InvalidFeeTreasury)]
+    pub fee_treasury: Account<'info, TokenAccount>,
+    /// Price history of the mint, the sale is recorded when it is passed
+    #[account(mut, seeds = [b"price_history", asset.key().as_ref(), bid_escrow.mint.as_ref()], bump = price_history.bump)]
+    pub price_history: Option<Account<'info, PriceHistory>>,
+    pub token_program: Program<'info, Token>,
+    pub nft_items_program: Program<'info, Nft>,
+    /// CHECK: Metaplex Core
+    #[account(address = MPL_CORE_ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePriceHistory<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PriceHistory::INIT_SPACE,
+        seeds = [b"price_history", mint.key().as_ref(), payment_mint.key().as_ref()],
+        bump
+    )]
+    pub price_history: Account<'info, PriceHistory>,
+    /// CHECK: NFT mint or Core asset the history tracks
+    pub mint: UncheckedAccount<'info>,
+    /// CHECK: Payment mint, or the System Program id for native SOL
+    pub payment_mint: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(bundle_id: u64)]
+pub struct ListBundle<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + BundleListing::INIT_SPACE,
+        seeds = [b"bundle", seller.key().as_ref(), &bundle_id.to_le_bytes()],
+        bump
+    )]
+    pub bundle: Account<'info, BundleListing>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub nft_items_program: Program<'info, Nft>,
+    /// CHECK: Metaplex Core
+    #[account(address = MPL_CORE_ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyBundle<'info> {
+    #[account(
+        mut,
+        close = seller,
+        seeds = [b"bundle", bundle.seller.as_ref(), &bundle.bundle_id.to_le_bytes()],
+        bump = bundle.bump,
+        has_one = seller
+    )]
+    pub bundle: Account<'info, BundleListing>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    /// Buyer's token account in the bundle's payment mint, omitted for native SOL
+    #[account(mut, token::authority = buyer)]
+    pub buyer_payment_token: Option<Account<'info, TokenAccount>>,
+    /// CHECK: Seller's address, validated by the bundle account
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    /// CHECK: Seller's token account in the payment mint, or the seller wallet for native SOL. Checked when paid
+    #[account(mut)]
+    pub seller_payment: UncheckedAccount<'info>,
+    #[account(seeds = [b"marketplace_config"], bump = config.bump)]
+    pub config: Account<'info, MarketplaceConfig>,
+    /// CHECK: Treasury token account in the payment mint, or the treasury wallet for native SOL. Checked when paid
+    #[account(mut)]
+    pub fee_treasury: UncheckedAccount<'info>,
     pub token_program: Program<'info, Token>,
+    pub nft_items_program: Program<'info, Nft>,
+    /// CHECK: Metaplex Core
+    #[account(address = MPL_CORE_ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelBundle<'info> {
+    #[account(
+        mut,
+        close = seller,
+        seeds = [b"bundle", seller.key().as_ref(), &bundle.bundle_id.to_le_bytes()],
+        bump = bundle.bump,
+        has_one = seller
+    )]
+    pub bundle: Account<'info, BundleListing>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub nft_items_program: Program<'info, Nft>,
+    /// CHECK: Metaplex Core
+    #[account(address = MPL_CORE_ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
 }
@@ -1,11 +1,14 @@
 use anchor_lang::prelude::*;
+use nft_items::ItemType;
 mod states;
 mod instructions;
 mod errors;
+mod events;
 
 pub use states::*;
 pub use instructions::*;
 pub use errors::*;
+pub use events::*;
 
 
 declare_id!("BjuQiWyhrmQd3JeWVLbkscM3mYZorti2Y9bDBtLPz4TU");
@@ -14,8 +17,17 @@ declare_id!("BjuQiWyhrmQd3JeWVLbkscM3mYZorti2Y9bDBtLPz4TU");
 pub mod programs_marketplace {
     use super::*;
 
-   pub fn list_nft(ctx: Context<ListNft>, price: u64) -> Result<()> {
-        instructions::list_nft(ctx, price)
+    pub fn initialize_marketplace(ctx: Context<InitializeMarketplace>, fee_bps: u16, payment_mints: Vec<Pubkey>) -> Result<()> {
+        instructions::initialize_marketplace(ctx, fee_bps, payment_mints)
+    }
+    pub fn update_fee_config(ctx: Context<UpdateFeeConfig>, fee_bps: u16) -> Result<()> {
+        instructions::update_fee_config(ctx, fee_bps)
+    }
+    pub fn set_payment_mints(ctx: Context<SetPaymentMints>, payment_mints: Vec<Pubkey>) -> Result<()> {
+        instructions::set_payment_mints(ctx, payment_mints)
+    }
+   pub fn list_nft(ctx: Context<ListNft>, price: u64, payment_mint: Pubkey, expires_at: Option<i64>) -> Result<()> {
+        instructions::list_nft(ctx, price, payment_mint, expires_at)
     }
     pub fn update_listing(ctx: Context<UpdateListing>, new_price: u64, is_active: bool) -> Result<()> {
         instructions::update_listing(ctx, new_price, is_active)
@@ -26,4 +38,90 @@ pub mod programs_marketplace {
     pub fn buy_nft(ctx: Context<BuyNft>) -> Result<()> {
         instructions::buy_nft(ctx)
     }
+    pub fn close_expired_listing(ctx: Context<CloseExpiredListing>) -> Result<()> {
+        instructions::close_expired_listing(ctx)
+    }
+    pub fn list_core_asset(ctx: Context<ListCoreAsset>, price: u64, payment_mint: Pubkey, expires_at: Option<i64>) -> Result<()> {
+        instructions::list_core_asset(ctx, price, payment_mint, expires_at)
+    }
+    pub fn buy_core_asset<'info>(ctx: Context<'_, '_, 'info, 'info, BuyCoreAsset<'info>>) -> Result<()> {
+        instructions::buy_core_asset(ctx)
+    }
+    pub fn cancel_core_listing(ctx: Context<CancelCoreListing>) -> Result<()> {
+        instructions::cancel_core_listing(ctx)
+    }
+    pub fn close_expired_core_listing(ctx: Context<CloseExpiredCoreListing>) -> Result<()> {
+        instructions::close_expired_core_listing(ctx)
+    }
+    pub fn create_auction(
+        ctx: Context<CreateAuction>,
+        kind: AuctionKind,
+        start_price: u64,
+        reserve_price: u64,
+        min_increment: u64,
+        duration: i64,
+        extension_window: i64,
+    ) -> Result<()> {
+        instructions::create_auction(ctx, kind, start_price, reserve_price, min_increment, duration, extension_window)
+    }
+    pub fn place_bid(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+        instructions::place_bid(ctx, amount)
+    }
+    pub fn settle_auction(ctx: Context<SettleAuction>) -> Result<()> {
+        instructions::settle_auction(ctx)
+    }
+    pub fn buy_dutch_auction(ctx: Context<BuyDutchAuction>) -> Result<()> {
+        instructions::buy_dutch_auction(ctx)
+    }
+    pub fn cancel_auction(ctx: Context<CancelAuction>) -> Result<()> {
+        instructions::cancel_auction(ctx)
+    }
+    pub fn make_offer(ctx: Context<MakeOffer>, amount: u64) -> Result<()> {
+        instructions::make_offer(ctx, amount)
+    }
+    pub fn cancel_offer(ctx: Context<CancelOffer>) -> Result<()> {
+        instructions::cancel_offer(ctx)
+    }
+    pub fn accept_offer(ctx: Context<AcceptOffer>) -> Result<()> {
+        instructions::accept_offer(ctx)
+    }
+    pub fn make_core_offer(ctx: Context<MakeCoreOffer>, amount: u64) -> Result<()> {
+        instructions::make_core_offer(ctx, amount)
+    }
+    pub fn accept_core_offer<'info>(ctx: Context<'_, '_, 'info, 'info, AcceptCoreOffer<'info>>) -> Result<()> {
+        instructions::accept_core_offer(ctx)
+    }
+    pub fn make_collection_bid(
+        ctx: Context<MakeCollectionBid>,
+        bid_id: u64,
+        price: u64,
+        quantity: u32,
+        item_type: Option<ItemType>,
+        min_rarity: u8,
+    ) -> Result<()> {
+        instructions::make_collection_bid(ctx, bid_id, price, quantity, item_type, min_rarity)
+    }
+    pub fn cancel_collection_bid(ctx: Context<CancelCollectionBid>) -> Result<()> {
+        instructions::cancel_collection_bid(ctx)
+    }
+    pub fn fill_collection_bid<'info>(ctx: Context<'_, '_, 'info, 'info, FillCollectionBid<'info>>) -> Result<()> {
+        instructions::fill_collection_bid(ctx)
+    }
+    pub fn initialize_price_history(ctx: Context<InitializePriceHistory>) -> Result<()> {
+        instructions::initialize_price_history(ctx)
+    }
+    pub fn list_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ListBundle<'info>>,
+        bundle_id: u64,
+        payment_mint: Pubkey,
+        items: Vec<BundleItem>,
+    ) -> Result<()> {
+        instructions::list_bundle(ctx, bundle_id, payment_mint, items)
+    }
+    pub fn buy_bundle<'info>(ctx: Context<'_, '_, 'info, 'info, BuyBundle<'info>>) -> Result<()> {
+        instructions::buy_bundle(ctx)
+    }
+    pub fn cancel_bundle<'info>(ctx: Context<'_, '_, 'info, 'info, CancelBundle<'info>>) -> Result<()> {
+        instructions::cancel_bundle(ctx)
+    }
 }
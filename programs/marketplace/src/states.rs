@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use nft_items::ItemType;
 
 
 
@@ -9,8 +10,185 @@ pub struct Listing{
     pub price: u64,
     pub is_active: bool,
     pub bump: u8,
+    pub standard: AssetStandard,
+    pub payment_mint: Pubkey,
+    pub expires_at: Option<i64>,
 }
 
 impl Listing {
-    pub const INIT_SPACE: usize = 32 + 32 + 8 + 1 + 1; // 74 bytes
+    pub const INIT_SPACE: usize = 32 + 32 + 8 + 1 + 1 + 1 + 32 + (1 + 8); // 116 bytes
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+}
+
+// Listings priced in native SOL use the System Program id as their payment mint
+pub const NATIVE_SOL: Pubkey = Pubkey::new_from_array([0; 32]);
+
+#[account]
+pub struct MarketplaceConfig {
+    pub admin: Pubkey,
+    pub fee_bps: u16,
+    pub fee_treasury: Pubkey,      // Wallet that owns the fee token accounts, or receives SOL fees directly
+    pub bump: u8,
+    pub payment_mints: Vec<Pubkey>, // Mints sales can be priced in, NATIVE_SOL for lamports
+}
+
+impl MarketplaceConfig {
+    pub const MAX_PAYMENT_MINTS: usize = 8;
+    pub const INIT_SPACE: usize = 32 + 2 + 32 + 1 + (4 + 32 * Self::MAX_PAYMENT_MINTS); // 327 bytes
+    pub const MAX_FEE_BPS: u16 = 1_000;
+
+    pub fn accepts(&self, mint: &Pubkey) -> bool {
+        self.payment_mints.contains(mint)
+    }
+}
+
+#[account]
+pub struct Auction {
+    pub nft_mint: Pubkey,
+    pub seller: Pubkey,
+    pub kind: AuctionKind,
+    pub start_price: u64,      // English: minimum opening bid, Dutch: price at start_time
+    pub reserve_price: u64,    // English: reserve, Dutch: floor reached at end_time
+    pub min_increment: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub extension_window: i64, // Bids this close to end_time push it back by the window
+    pub highest_bid: u64,
+    pub highest_bidder: Pubkey,
+    pub bump: u8,
+}
+
+impl Auction {
+    pub const INIT_SPACE: usize = 32 + 32 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 32 + 1; // 154 bytes
+
+    pub fn has_bid(&self) -> bool {
+        self.highest_bidder != Pubkey::default()
+    }
+
+    // Linear decay from start_price to reserve_price, then holds at the floor
+    pub fn dutch_price(&self, now: i64) -> u64 {
+        if now <= self.start_time {
+            return self.start_price;
+        }
+        if now >= self.end_time {
+            return self.reserve_price;
+        }
+        let elapsed = (now - self.start_time) as u128;
+        let duration = (self.end_time - self.start_time) as u128;
+        let drop = (self.start_price - self.reserve_price) as u128;
+        self.start_price - (drop * elapsed / duration) as u64
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AuctionKind {
+    English,
+    Dutch,
+}
+
+// Offers on Core assets store the asset in nft_mint, the escrowed payment works the same for both
+#[account]
+pub struct Offer {
+    pub nft_mint: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+impl Offer {
+    pub const INIT_SPACE: usize = 32 + 32 + 8 + 1; // 73 bytes
+}
+
+// Standing bid for any item of a Core collection, escrowing price * quantity
+#[account]
+pub struct CollectionBid {
+    pub buyer: Pubkey,
+    pub collection: Pubkey,
+    pub bid_id: u64,
+    pub price: u64,
+    pub quantity: u32,
+    pub item_type: Option<ItemType>,
+    pub min_rarity: u8,
+    pub bump: u8,
+}
+
+impl CollectionBid {
+    pub const INIT_SPACE: usize = 32 + 32 + 8 + 8 + 4 + (1 + 1) + 1 + 1; // 88 bytes
+}
+
+// Last sale prices of one mint in one payment mint, oldest entries overwritten first
+#[account]
+pub struct PriceHistory {
+    pub mint: Pubkey,
+    pub payment_mint: Pubkey,
+    pub prices: [u64; PriceHistory::CAPACITY],
+    pub next: u8, // Slot the next sale is written to
+    pub len: u8,
+    pub bump: u8,
+}
+
+impl PriceHistory {
+    pub const CAPACITY: usize = 16;
+    pub const INIT_SPACE: usize = 32 + 32 + 8 * Self::CAPACITY + 1 + 1 + 1; // 195 bytes
+
+    pub fn record(&mut self, price: u64) {
+        self.prices[self.next as usize] = price;
+        self.next = ((self.next as usize + 1) % Self::CAPACITY) as u8;
+        self.len = (self.len as usize + 1).min(Self::CAPACITY) as u8;
+    }
+
+    pub fn recorded(&self) -> &[u64] {
+        &self.prices[..self.len as usize]
+    }
+
+    pub fn floor(&self) -> Option<u64> {
+        self.recorded().iter().copied().min()
+    }
+
+    pub fn average(&self) -> Option<u64> {
+        let recorded = self.recorded();
+        if recorded.is_empty() {
+            return None;
+        }
+        let total: u128 = recorded.iter().map(|&price| price as u128).sum();
+        Some((total / recorded.len() as u128) as u64)
+    }
+}
+
+// Several items sold together, the price is the sum of the item prices. SPL items sit in the bundle's associated token accounts,
+// Core items are frozen in place with the bundle PDA as delegate
+#[account]
+pub struct BundleListing {
+    pub seller: Pubkey,
+    pub bundle_id: u64,
+    pub price: u64,
+    pub payment_mint: Pubkey,
+    pub items: Vec<BundleItem>,
+    pub bump: u8,
+}
+
+impl BundleListing {
+    pub const MAX_ITEMS: usize = 5;
+    pub const INIT_SPACE: usize = 32 + 8 + 8 + 32 + (4 + BundleItem::SIZE * Self::MAX_ITEMS) + 1; // 290 bytes
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct BundleItem {
+    pub standard: AssetStandard,
+    pub mint: Pubkey, // NFT mint, or the asset for Core items
+    pub price: u64, // Share of the bundle price, royalties are paid on it
+}
+
+impl BundleItem {
+    pub const SIZE: usize = 1 + 32 + 8;
+}
+
+// SPL listings escrow the token, Core listings (nft_mint holds the asset) are frozen in place
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AssetStandard {
+    SplToken,
+    Core,
 }
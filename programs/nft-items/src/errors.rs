@@ -21,5 +21,27 @@ pub enum GameError {
     #[msg("Missing boss proof data")]
     MissingBossProof,
     #[msg("Missing treasury proof data")]
-    MissingTreasuryProof
+    MissingTreasuryProof,
+    #[msg("Signer is not the marketplace listing for this asset")]
+    UnauthorizedMarketplace,
+    #[msg("Asset account could not be read")]
+    InvalidAsset,
+    #[msg("Signer is not the collection authority")]
+    UnauthorizedAuthority,
+    #[msg("No authority transfer is pending for this signer")]
+    NoPendingAuthority,
+    #[msg("Signer is not the fighter that owns this asset")]
+    UnauthorizedFighter,
+    #[msg("Item is not equipped")]
+    NotEquipped,
+    #[msg("Fighter account does not belong to the proof's player")]
+    InvalidFighter,
+    #[msg("Player has not conquered every island")]
+    IslandsNotConquered,
+    #[msg("Treasury proof is for a different player")]
+    TreasuryPlayerMismatch,
+    #[msg("Account is not a collection")]
+    InvalidCollection,
+    #[msg("Collection already has the current layout")]
+    AlreadyMigrated,
 }
@@ -1,12 +1,20 @@
 use anchor_lang::prelude::*;
+use anchor_lang::{pubkey, Discriminator};
 use mpl_core::{
     ID as MPL_CORE_ID,
+    accounts::BaseAssetV1,
     instructions::{CreateCollectionV2CpiBuilder, CreateV2CpiBuilder},
     types::{Attribute, Attributes, Plugin, PluginAuthorityPair},
 };
 use crate::state::*;
 use crate::errors::*;
 
+// Marketplace program allowed to flag items as listed through its listing PDAs
+pub const MARKETPLACE_PROGRAM_ID: Pubkey = pubkey!("BjuQiWyhrmQd3JeWVLbkscM3mYZorti2Y9bDBtLPz4TU");
+
+// Fighter registry program allowed to flag items as equipped through its fighter PDAs
+pub const FIGHTER_REGISTRY_PROGRAM_ID: Pubkey = pubkey!("8iAqcZnZgfVxSSm8C9UtHadJcSNqvuYaGrQ3XuCnehj7");
+
 // Initialize a Collection Asset (Metaplex Core collection)
 
  pub fn initialize_collection(
@@ -28,6 +36,45 @@ Result<()> {
         collection.total_minted = 0;
         collection.name = name;
         collection.uri = uri;
+        collection.pending_authority = None;
+        Ok(())
+    }
+
+    // Two-step handover: proposing again replaces the pending authority, None cancels it
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Option<Pubkey>) -> Result<()> {
+        ctx.accounts.collection.pending_authority = new_authority;
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let collection = &mut ctx.accounts.collection;
+        collection.authority = ctx.accounts.new_authority.key();
+        collection.pending_authority = None;
+        Ok(())
+    }
+
+    // Grows a collection created before pending_authority existed. The new bytes are zeroed, which reads as None
+    pub fn migrate_collection(ctx: Context<MigrateCollection>) -> Result<()> {
+        let collection = ctx.accounts.collection.to_account_info();
+        {
+            let data = collection.try_borrow_data()?;
+            require!(data.len() >= 40 && data[..8] == Collection::DISCRIMINATOR, GameError::InvalidCollection);
+            let authority = Pubkey::try_from(&data[8..40]).map_err(|_| error!(GameError::InvalidCollection))?;
+            require_keys_eq!(authority, ctx.accounts.authority.key(), GameError::UnauthorizedAuthority);
+        }
+
+        let new_len = 8 + Collection::INIT_SPACE;
+        require!(collection.data_len() < new_len, GameError::AlreadyMigrated);
+
+        let rent = Rent::get()?.minimum_balance(new_len).saturating_sub(collection.lamports());
+        if rent > 0 {
+            let cpi_accounts = anchor_lang::system_program::Transfer {
+                from: ctx.accounts.authority.to_account_info(),
+                to: collection.clone(),
+            };
+            anchor_lang::system_program::transfer(CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts), rent)?;
+        }
+        collection.realloc(new_len, true)?;
         Ok(())
     }
 
@@ -149,7 +196,20 @@ Result<()> {
         treasury_proof: Option<TreasuryProof>,
     ) -> Result<()> {
         require!(rarity == 5, GameError::TreasuryDropNotLegendary);
-        require!(treasury_proof.is_some(), GameError::MissingTreasuryProof);
+        let mut proof = treasury_proof.ok_or(GameError::MissingTreasuryProof)?;
+        // The drop goes to the player who earned it, and only once
+        require_keys_eq!(proof.player, ctx.accounts.owner.key(), GameError::TreasuryPlayerMismatch);
+
+        // all_islands_conquered comes from the player's fighter, not the client
+        let (expected_fighter, _) = Pubkey::find_program_address(
+            &[b"fighter", proof.player.as_ref()],
+            &FIGHTER_REGISTRY_PROGRAM_ID,
+        );
+        require_keys_eq!(ctx.accounts.fighter.key(), expected_fighter, GameError::InvalidFighter);
+        let progress = FighterProgress::try_read(&ctx.accounts.fighter.to_account_info())?;
+        require!(progress.defeated_bosses == FighterProgress::ALL_ISLANDS, GameError::IslandsNotConquered);
+        proof.all_islands_conquered = true;
+        let treasury_proof = Some(proof);
 
         CreateV2CpiBuilder::new(&ctx.accounts.mpl_core_program.to_account_info())
             .asset(&ctx.accounts.asset.to_account_info())
@@ -190,6 +250,57 @@ Result<()> {
         game_item.boss_proof = None;
         game_item.treasury_proof = treasury_proof;
         game_item.created_at = Clock::get()?.unix_timestamp;
+
+        let treasury_claim = &mut ctx.accounts.treasury_claim;
+        treasury_claim.player = ctx.accounts.owner.key();
+        treasury_claim.asset = ctx.accounts.asset.key();
+        treasury_claim.claimed_at = game_item.created_at;
+        treasury_claim.bump = ctx.bumps.treasury_claim;
+        Ok(())
+    }
+
+    // Called by the marketplace via CPI: the listing PDA signs, and the owner is re-read from the asset
+    pub fn sync_listing_state(ctx: Context<SyncListingState>, is_listed: bool) -> Result<()> {
+        let (expected_listing, _) = Pubkey::find_program_address(
+            &[b"listing", ctx.accounts.asset.key().as_ref()],
+            &MARKETPLACE_PROGRAM_ID,
+        );
+        require_keys_eq!(ctx.accounts.listing.key(), expected_listing, GameError::UnauthorizedMarketplace);
+
+        let asset = BaseAssetV1::try_from(&ctx.accounts.asset.to_account_info())
+            .map_err(|_| error!(GameError::InvalidAsset))?;
+
+        let game_item = &mut ctx.accounts.game_item;
+        if is_listed {
+            require!(!game_item.is_equipped, GameError::AlreadyEquipped);
+            require!(!game_item.is_listed, GameError::AlreadyListed);
+        }
+        game_item.owner = asset.owner;
+        game_item.is_listed = is_listed;
+        Ok(())
+    }
+
+    // Called by the fighter registry via CPI: the fighter PDA of the equipping owner signs.
+    // Unequipping is signed by the fighter recorded at equip time, even if the asset has since moved.
+    pub fn sync_equipped_state(ctx: Context<SyncEquippedState>, is_equipped: bool) -> Result<()> {
+        let game_item = &mut ctx.accounts.game_item;
+        if is_equipped {
+            let asset = BaseAssetV1::try_from(&ctx.accounts.asset.to_account_info())
+                .map_err(|_| error!(GameError::InvalidAsset))?;
+            require!(!game_item.is_equipped, GameError::AlreadyEquipped);
+            require!(!game_item.is_listed, GameError::AlreadyListed);
+            game_item.owner = asset.owner;
+        } else {
+            require!(game_item.is_equipped, GameError::NotEquipped);
+        }
+
+        let (expected_fighter, _) = Pubkey::find_program_address(
+            &[b"fighter", game_item.owner.as_ref()],
+            &FIGHTER_REGISTRY_PROGRAM_ID,
+        );
+        require_keys_eq!(ctx.accounts.fighter.key(), expected_fighter, GameError::UnauthorizedFighter);
+
+        game_item.is_equipped = is_equipped;
         Ok(())
     }
 
@@ -219,6 +330,44 @@ pub struct InitializeCollection<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"collection"],
+        bump,
+        has_one = authority @ GameError::UnauthorizedAuthority
+    )]
+    pub collection: Account<'info, Collection>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"collection"],
+        bump,
+        constraint = collection.pending_authority == Some(new_authority.key()) @ GameError::NoPendingAuthority
+    )]
+    pub collection: Account<'info, Collection>,
+
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateCollection<'info> {
+    /// CHECK: Collection in its old layout, which Account<Collection> may not read; checked in the handler
+    #[account(mut, seeds = [b"collection"], bump, owner = crate::ID)]
+    pub collection: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct MintGameItem<'info> {
     #[account(
@@ -323,8 +472,18 @@ pub struct MintTreasuryDrop<'info> {
     pub asset: Signer<'info>,
     #[account(mut)]
     pub payer: Signer<'info>,
-    /// CHECK: Owner
-    pub owner: UncheckedAccount<'info>,
+    pub owner: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TreasuryClaim::INIT_SPACE,
+        seeds = [b"treasury_claim", owner.key().as_ref()],
+        bump
+    )]
+    pub treasury_claim: Account<'info, TreasuryClaim>,
+    /// CHECK: fighter_registry Fighter of the proof's player, read in the handler
+    #[account(owner = FIGHTER_REGISTRY_PROGRAM_ID)]
+    pub fighter: UncheckedAccount<'info>,
 
     /// CHECK: The Metaplex Core program ID, required for CPIs.
     #[account(address = MPL_CORE_ID)]
@@ -332,3 +491,39 @@ pub struct MintTreasuryDrop<'info> {
     pub system_program: Program<'info, System>,
 
 }
+
+#[derive(Accounts)]
+pub struct SyncListingState<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_item", asset.key().as_ref()],
+        bump,
+        has_one = asset
+    )]
+    pub game_item: Account<'info, GameItem>,
+
+    /// CHECK: Core asset, owner is read from its data
+    #[account(owner = MPL_CORE_ID)]
+    pub asset: UncheckedAccount<'info>,
+
+    /// Marketplace listing PDA for the asset, checked in the handler
+    pub listing: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SyncEquippedState<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_item", asset.key().as_ref()],
+        bump,
+        has_one = asset
+    )]
+    pub game_item: Account<'info, GameItem>,
+
+    /// CHECK: Core asset, owner is read from its data
+    #[account(owner = MPL_CORE_ID)]
+    pub asset: UncheckedAccount<'info>,
+
+    /// Fighter registry PDA of the item owner, checked in the handler
+    pub fighter: Signer<'info>,
+}
@@ -22,6 +22,18 @@ pub mod nft {
         instructions::initialize_collection(ctx, name, uri)
     }
 
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Option<Pubkey>) -> Result<()> {
+        instructions::propose_authority(ctx, new_authority)
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        instructions::accept_authority(ctx)
+    }
+
+    pub fn migrate_collection(ctx: Context<MigrateCollection>) -> Result<()> {
+        instructions::migrate_collection(ctx)
+    }
+
     pub fn mint_game_item(
         ctx: Context<MintGameItem>,
         name: String,
@@ -56,4 +68,12 @@ pub mod nft {
     ) -> Result<()> {
         instructions::mint_treasury_drop(ctx, name, uri, item_type, rarity, base_stats, treasury_proof)
     }
+
+    pub fn sync_listing_state(ctx: Context<SyncListingState>, is_listed: bool) -> Result<()> {
+        instructions::sync_listing_state(ctx, is_listed)
+    }
+
+    pub fn sync_equipped_state(ctx: Context<SyncEquippedState>, is_equipped: bool) -> Result<()> {
+        instructions::sync_equipped_state(ctx, is_equipped)
+    }
 }
\ No newline at end of file
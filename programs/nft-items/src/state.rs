@@ -9,6 +9,7 @@ pub struct Collection {
     pub name: String,
     #[max_len(100)]
     pub uri: String,
+    pub pending_authority: Option<Pubkey>, // Proposed authority, takes over once it accepts
 
     
 }
@@ -32,6 +33,16 @@ pub struct GameItem {
     pub bump: u8,
 }
 
+// One per player, so the treasury drop can only be claimed once
+#[account]
+#[derive(InitSpace)]
+pub struct TreasuryClaim {
+    pub player: Pubkey,
+    pub asset: Pubkey,
+    pub claimed_at: i64,
+    pub bump: u8,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct Listing {
@@ -60,7 +71,7 @@ pub struct BossProof {
     pub player : Pubkey,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone,Copy,InitSpace, Debug)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone,Copy,InitSpace, Debug, PartialEq, Eq)]
 pub enum ItemType {
    Weapon,
     Ship,
@@ -73,6 +84,44 @@ pub enum ItemType {
 pub struct TreasuryProof {
     pub claim_timestamp: i64,
     pub player: Pubkey,
-    pub all_islands_conquered: bool,  // Must have beaten all 7 bosses
+    pub all_islands_conquered: bool,  // Set from the fighter: every island boss (4) beaten
     pub final_battle_score: u32,      // PvP ranking
+}
+
+// Leading fields of fighter_registry's Fighter, mirrored so treasury proofs can be
+// checked without depending on that crate (it already depends on this one).
+// fighter_registry's state tests parse a real Fighter through this to catch drift
+#[derive(AnchorDeserialize)]
+pub struct FighterProgress {
+    pub authority: Pubkey,
+    pub name: String,
+    pub health: u16,
+    pub stamina: u16,
+    pub experience: u32,
+    pub level: u8,
+    pub tokens: u64,
+    pub equipment: [Option<FighterEquippedItem>; 5],
+    pub current_island: u8,
+    pub unlocked_islands: u8,
+    pub defeated_bosses: u8,
+}
+
+#[derive(AnchorDeserialize)]
+pub struct FighterEquippedItem {
+    pub asset: Pubkey,
+    pub stats: ItemStats,
+}
+
+impl FighterProgress {
+    pub const ALL_ISLANDS: u8 = 0b1111;
+
+    pub fn try_read(info: &AccountInfo) -> Result<Self> {
+        Self::try_from_data(&info.try_borrow_data()?)
+    }
+
+    pub fn try_from_data(data: &[u8]) -> Result<Self> {
+        let discriminator = anchor_lang::solana_program::hash::hash(b"account:Fighter").to_bytes();
+        require!(data.len() > 8 && data[..8] == discriminator[..8], crate::errors::GameError::InvalidFighter);
+        Self::deserialize(&mut &data[8..]).map_err(|_| error!(crate::errors::GameError::InvalidFighter))
+    }
 }
\ No newline at end of file
@@ -14,4 +14,92 @@ pub enum ErrorCode {
     InvalidLevel,
     #[msg("Invalid treasure type")]
     InvalidTreasure,
+    #[msg("Account already has this role")]
+    RoleAlreadyGranted,
+    #[msg("Account does not have this role")]
+    RoleNotFound,
+    #[msg("Role has too many members")]
+    TooManyRoleMembers,
+    #[msg("Daily login bonus was already claimed in the last 24 hours")]
+    LoginBonusNotReady,
+    #[msg("Login bonus schedule must have between 1 and 30 days and a non-zero streak cap")]
+    InvalidLoginSchedule,
+    #[msg("Reward was already claimed by this player")]
+    RewardAlreadyClaimed,
+    #[msg("Reward schedule exceeds the maximum levels or treasure types")]
+    InvalidRewardSchedule,
+    #[msg("Minting would exceed the PIRATE max supply")]
+    MaxSupplyExceeded,
+    #[msg("Max supply cannot be below the current total supply")]
+    InvalidMaxSupply,
+    #[msg("Emission budget for this reward category is exhausted for the current epoch")]
+    EmissionBudgetExhausted,
+    #[msg("Emission epoch length must be positive")]
+    InvalidEmissionBudget,
+    #[msg("Stake pool parameters are invalid")]
+    InvalidStakePool,
+    #[msg("Lock boost does not exist in this pool")]
+    InvalidLockBoost,
+    #[msg("Cannot switch a stake position to a smaller lock boost")]
+    LockBoostDowngrade,
+    #[msg("Penalty treasury does not match the stake pool")]
+    InvalidPenaltyTreasury,
+    #[msg("No staking rewards to claim")]
+    NoRewardsToClaim,
+    #[msg("Vesting schedule needs start <= cliff <= end, at most 8 milestones and a non-zero total")]
+    InvalidVestingSchedule,
+    #[msg("No vested tokens to release")]
+    NothingToRelease,
+    #[msg("Vesting schedule is not revocable")]
+    VestingNotRevocable,
+    #[msg("Vesting schedule was revoked")]
+    VestingRevoked,
+    #[msg("Milestone does not exist or is already unlocked")]
+    InvalidMilestone,
+    #[msg("Treasury does not match the vesting schedule")]
+    VestingTreasuryMismatch,
+    #[msg("Vault still holds tokens")]
+    VaultNotEmpty,
+    #[msg("Amount exceeds the delegate's allowance")]
+    AllowanceExceeded,
+    #[msg("Allowance has expired")]
+    AllowanceExpired,
+    #[msg("Reward must be preceded by an ed25519 instruction with a game server attestation")]
+    MissingAttestation,
+    #[msg("Attestation is not signed by a registered game server")]
+    UnknownGameServer,
+    #[msg("Attestation does not match this reward")]
+    AttestationMismatch,
+    #[msg("Attestation has expired")]
+    AttestationExpired,
+    #[msg("Attestation nonce was already used")]
+    AttestationReplayed,
+    #[msg("Distributor needs a non-zero total, at most 65536 nodes and a future clawback time")]
+    InvalidDistributor,
+    #[msg("Merkle proof is invalid")]
+    InvalidMerkleProof,
+    #[msg("Airdrop was already claimed")]
+    AirdropAlreadyClaimed,
+    #[msg("Claims exceed the distributor total")]
+    DistributorExhausted,
+    #[msg("Clawback deadline has not passed yet")]
+    ClawbackNotStarted,
+    #[msg("Unclaimed airdrop tokens were clawed back")]
+    DistributorClawedBack,
+    #[msg("No authority transfer is pending for this signer")]
+    NoPendingAuthority,
+    #[msg("Admin set needs 1 to 10 unique members and a threshold between 1 and the member count")]
+    InvalidAdminSet,
+    #[msg("Admin set is already enabled")]
+    AdminSetAlreadyEnabled,
+    #[msg("Signer is not a member of the admin set")]
+    NotAdminMember,
+    #[msg("This instruction needs an approved proposal from the admin set")]
+    ProposalRequired,
+    #[msg("Proposal does not match this instruction and its arguments")]
+    ProposalMismatch,
+    #[msg("Proposal has not reached the approval threshold")]
+    ProposalNotApproved,
+    #[msg("Proposal was already approved by this member or already executed")]
+    ProposalAlreadyUsed,
 }
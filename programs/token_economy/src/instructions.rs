@@ -1,150 +1,1835 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::Mint;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::solana_program::sysvar::instructions::{self as instructions_sysvar, load_current_index_checked, load_instruction_at_checked};
+use anchor_spl::token::{
+    self, spl_token::instruction::AuthorityType, Burn, CloseAccount, Mint, MintTo, SetAuthority, Token, TokenAccount, Transfer,
+};
 use crate::errors::ErrorCode;
-use crate::state::{Pirate, Vault};
+use crate::state::{
+    AdminAction, AdminSet, Allowance, ClaimReceipt, CreateDistributorParams, CreatePoolParams, CreateVestingParams, EmissionBudget, LoginBonusConfig, LoginTracker, MerkleDistributor, Pirate, Proposal, RewardCategory, RewardSchedule,
+    RewardAttestation, RewardScheduleParams, RewardSource, Role, RoleConfig, StakePool, StakePosition, Vault,
+    VestingSchedule,
+};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Accounts)]
+pub struct InitializePirate<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 1 + 8 + 1 + (1 + 8) + (1 + 32) + 1,
+        seeds = [b"pirate", mint.key().as_ref()],
+        bump
+    )]
+    pub pirate: Account<'info, Pirate>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 3 * (4 + 32 * RoleConfig::MAX_MEMBERS) + 1,
+        seeds = [b"roles", pirate.key().as_ref()],
+        bump
+    )]
+    pub roles: Account<'info, RoleConfig>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageRole<'info> {
+    #[account(
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        constraint = pirate.uses_admin_set || pirate.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub pirate: Account<'info, Pirate>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    #[account(mut, seeds = [b"roles", pirate.key().as_ref()], bump = roles.bump)]
+    pub roles: Account<'info, RoleConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(mut, seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump, has_one = authority @ ErrorCode::Unauthorized)]
+    pub pirate: Account<'info, Pirate>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        constraint = pirate.pending_authority == Some(new_authority.key()) @ ErrorCode::NoPendingAuthority
+    )]
+    pub pirate: Account<'info, Pirate>,
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAdminSet<'info> {
+    #[account(
+        mut,
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = !pirate.uses_admin_set @ ErrorCode::AdminSetAlreadyEnabled
+    )]
+    pub pirate: Account<'info, Pirate>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + (4 + 32 * AdminSet::MAX_MEMBERS) + 1 + 8 + 1,
+        seeds = [b"admin_set", pirate.key().as_ref()],
+        bump
+    )]
+    pub admin_set: Account<'info, AdminSet>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAdminSet<'info> {
+    #[account(seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump)]
+    pub pirate: Account<'info, Pirate>,
+    #[account(mut, seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Account<'info, AdminSet>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Account<'info, Proposal>,
+    pub executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump)]
+    pub pirate: Account<'info, Pirate>,
+    #[account(
+        mut,
+        seeds = [b"admin_set", pirate.key().as_ref()],
+        bump = admin_set.bump,
+        constraint = admin_set.members.contains(&proposer.key()) @ ErrorCode::NotAdminMember
+    )]
+    pub admin_set: Account<'info, AdminSet>,
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + 32 + 8 + 1 + 32 + (4 + 32 * AdminSet::MAX_MEMBERS) + 1 + 1,
+        seeds = [b"proposal", pirate.key().as_ref(), &admin_set.proposal_count.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    #[account(seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump)]
+    pub pirate: Account<'info, Pirate>,
+    #[account(
+        seeds = [b"admin_set", pirate.key().as_ref()],
+        bump = admin_set.bump,
+        constraint = admin_set.members.contains(&member.key()) @ ErrorCode::NotAdminMember
+    )]
+    pub admin_set: Account<'info, AdminSet>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Account<'info, Proposal>,
+    pub member: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMaxSupply<'info> {
+    #[account(
+        mut,
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        constraint = pirate.uses_admin_set || pirate.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub pirate: Account<'info, Pirate>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(category: RewardCategory)]
+pub struct SetEmissionBudget<'info> {
+    #[account(
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        constraint = pirate.uses_admin_set || pirate.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub pirate: Account<'info, Pirate>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 1 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"emission", pirate.key().as_ref(), &[category as u8]],
+        bump
+    )]
+    pub emission_budget: Account<'info, EmissionBudget>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MintPirateTokens<'info> {
+    #[account(mut, seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump, has_one = mint)]
+    pub pirate: Account<'info, Pirate>,
+    #[account(
+        seeds = [b"roles", pirate.key().as_ref()],
+        bump = roles.bump,
+        constraint = roles.has_role(Role::Minter, &authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub roles: Account<'info, RoleConfig>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    pub to_vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", to_vault.key().as_ref()], bump)]
+    pub to_vault_tokens: Account<'info, TokenAccount>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BurnPirateTokens<'info> {
+    #[account(mut, seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump, has_one = mint)]
+    pub pirate: Account<'info, Pirate>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    pub from_vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", from_vault.key().as_ref()], bump)]
+    pub from_vault_tokens: Account<'info, TokenAccount>,
+    // Required when authority is a delegate rather than the vault owner
+    #[account(mut, seeds = [b"allowance", from_vault.key().as_ref(), authority.key().as_ref()], bump = allowance.bump)]
+    pub allowance: Option<Account<'info, Allowance>>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct TransferPirateTokens<'info> {
+    #[account(seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump)]
+    pub pirate: Account<'info, Pirate>,
+    pub from_vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", from_vault.key().as_ref()], bump)]
+    pub from_vault_tokens: Account<'info, TokenAccount>,
+    pub to_vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", to_vault.key().as_ref()], bump)]
+    pub to_vault_tokens: Account<'info, TokenAccount>,
+    // Required when authority is a delegate rather than the vault owner
+    #[account(mut, seeds = [b"allowance", from_vault.key().as_ref(), authority.key().as_ref()], bump = allowance.bump)]
+    pub allowance: Option<Account<'info, Allowance>>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(level: u8, nonce: u64)]
+pub struct LevelReward<'info> {
+    #[account(mut, seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump, has_one = mint)]
+    pub pirate: Account<'info, Pirate>,
+    #[account(
+        seeds = [b"roles", pirate.key().as_ref()],
+        bump = roles.bump,
+        constraint = roles.has_role(Role::RewardSigner, &authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub roles: Account<'info, RoleConfig>,
+    /// CHECK: EmissionBudget of the category, read in the handler. Uncapped until set_emission_budget creates it
+    #[account(
+        mut,
+        seeds = [b"emission", pirate.key().as_ref(), &[RewardCategory::LevelCompletion as u8]],
+        bump
+    )]
+    pub emission_budget: UncheckedAccount<'info>,
+    #[account(seeds = [b"reward_schedule", pirate.key().as_ref()], bump = reward_schedule.bump)]
+    pub reward_schedule: Account<'info, RewardSchedule>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 8 + 1,
+        seeds = [b"level_receipt", pirate.key().as_ref(), from_vault.owner.as_ref(), &[level]],
+        bump
+    )]
+    pub receipt: Account<'info, ClaimReceipt>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 8 + 1,
+        seeds = [b"attestation", pirate.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub attestation_receipt: Account<'info, ClaimReceipt>,
+    /// CHECK: Instructions sysvar, read to find the game server's ed25519 instruction
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    pub from_vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", from_vault.key().as_ref()], bump)]
+    pub from_vault_tokens: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(treasure_type: u8, treasure_id: u64, nonce: u64)]
+pub struct TreasureReward<'info> {
+    #[account(mut, seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump, has_one = mint)]
+    pub pirate: Account<'info, Pirate>,
+    #[account(
+        seeds = [b"roles", pirate.key().as_ref()],
+        bump = roles.bump,
+        constraint = roles.has_role(Role::RewardSigner, &authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub roles: Account<'info, RoleConfig>,
+    /// CHECK: EmissionBudget of the category, read in the handler. Uncapped until set_emission_budget creates it
+    #[account(
+        mut,
+        seeds = [b"emission", pirate.key().as_ref(), &[RewardCategory::Treasure as u8]],
+        bump
+    )]
+    pub emission_budget: UncheckedAccount<'info>,
+    #[account(seeds = [b"reward_schedule", pirate.key().as_ref()], bump = reward_schedule.bump)]
+    pub reward_schedule: Account<'info, RewardSchedule>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 8 + 1,
+        seeds = [b"treasure_receipt", pirate.key().as_ref(), from_vault.owner.as_ref(), &treasure_id.to_le_bytes()],
+        bump
+    )]
+    pub receipt: Account<'info, ClaimReceipt>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 8 + 1,
+        seeds = [b"attestation", pirate.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub attestation_receipt: Account<'info, ClaimReceipt>,
+    /// CHECK: Instructions sysvar, read to find the game server's ed25519 instruction
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    pub from_vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", from_vault.key().as_ref()], bump)]
+    pub from_vault_tokens: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRewardSchedule<'info> {
+    #[account(
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        constraint = pirate.uses_admin_set || pirate.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub pirate: Account<'info, Pirate>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 8 + (4 + 8 * RewardSchedule::MAX_LEVELS) + 1 + (4 + 8 * RewardSchedule::MAX_TREASURE_TYPES) + 2 + 1,
+        seeds = [b"reward_schedule", pirate.key().as_ref()],
+        bump
+    )]
+    pub reward_schedule: Account<'info, RewardSchedule>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRewardSchedule<'info> {
+    #[account(
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        constraint = pirate.uses_admin_set || pirate.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub pirate: Account<'info, Pirate>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    #[account(mut, seeds = [b"reward_schedule", pirate.key().as_ref()], bump = reward_schedule.bump)]
+    pub reward_schedule: Account<'info, RewardSchedule>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct DailyLogin<'info> {
+    #[account(mut, seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump, has_one = mint)]
+    pub pirate: Account<'info, Pirate>,
+    #[account(
+        seeds = [b"roles", pirate.key().as_ref()],
+        bump = roles.bump,
+        constraint = roles.has_role(Role::RewardSigner, &authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub roles: Account<'info, RoleConfig>,
+    /// CHECK: EmissionBudget of the category, read in the handler. Uncapped until set_emission_budget creates it
+    #[account(
+        mut,
+        seeds = [b"emission", pirate.key().as_ref(), &[RewardCategory::DailyLogin as u8]],
+        bump
+    )]
+    pub emission_budget: UncheckedAccount<'info>,
+    #[account(seeds = [b"login_bonus", pirate.key().as_ref()], bump = login_bonus.bump)]
+    pub login_bonus: Account<'info, LoginBonusConfig>,
+    // One tracker per player, so extra vaults don't reset the 24h window
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 8 + 2 + 1,
+        seeds = [b"login_tracker", pirate.key().as_ref(), from_vault.owner.as_ref()],
+        bump
+    )]
+    pub login_tracker: Account<'info, LoginTracker>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 8 + 1,
+        seeds = [b"attestation", pirate.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub attestation_receipt: Account<'info, ClaimReceipt>,
+    /// CHECK: Instructions sysvar, read to find the game server's ed25519 instruction
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    pub from_vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", from_vault.key().as_ref()], bump)]
+    pub from_vault_tokens: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeLoginBonus<'info> {
+    #[account(
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        constraint = pirate.uses_admin_set || pirate.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub pirate: Account<'info, Pirate>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + (4 + 8 * LoginBonusConfig::MAX_SCHEDULE_DAYS) + 2 + 1,
+        seeds = [b"login_bonus", pirate.key().as_ref()],
+        bump
+    )]
+    pub login_bonus: Account<'info, LoginBonusConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateLoginBonus<'info> {
+    #[account(
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        constraint = pirate.uses_admin_set || pirate.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub pirate: Account<'info, Pirate>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    #[account(mut, seeds = [b"login_bonus", pirate.key().as_ref()], bump = login_bonus.bump)]
+    pub login_bonus: Account<'info, LoginBonusConfig>,
+    pub authority: Signer<'info>,
+}
+
+// One vault per owner and mint, derivable by clients
+#[derive(Accounts)]
+pub struct InitializeVault<'info> {
+    #[account(init, payer = owner, space = 8 + 32 + 8, seeds = [b"vault", mint.key().as_ref(), owner.key().as_ref()], bump)]
+    pub vault: Account<'info, Vault>,
+    #[account(seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump, has_one = mint)]
+    pub pirate: Account<'info, Pirate>,
+    pub mint: Account<'info, Mint>,
+    // Tokens of the vault, held by the Pirate PDA on the owner's behalf
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"vault_tokens", vault.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = pirate,
+    )]
+    pub vault_tokens: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct Approve<'info> {
+    #[account(has_one = owner @ ErrorCode::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + 32 + 32 + 8 + (1 + 8) + 1,
+        seeds = [b"allowance", vault.key().as_ref(), delegate.as_ref()],
+        bump
+    )]
+    pub allowance: Account<'info, Allowance>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Revoke<'info> {
+    #[account(has_one = owner @ ErrorCode::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"allowance", vault.key().as_ref(), allowance.delegate.as_ref()], bump = allowance.bump, close = owner)]
+    pub allowance: Account<'info, Allowance>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    #[account(seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump)]
+    pub pirate: Account<'info, Pirate>,
+    #[account(mut, has_one = owner @ ErrorCode::Unauthorized, close = owner)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", vault.key().as_ref()], bump)]
+    pub vault_tokens: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        has_one = mint,
+        constraint = pirate.uses_admin_set || pirate.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub pirate: Account<'info, Pirate>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"vault_tokens", vault.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = pirate,
+    )]
+    pub vault_tokens: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawPirateTokens<'info> {
+    #[account(seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump)]
+    pub pirate: Account<'info, Pirate>,
+    #[account(has_one = owner @ ErrorCode::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", vault.key().as_ref()], bump)]
+    pub vault_tokens: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pirate.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DepositPirateTokens<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", vault.key().as_ref()], bump)]
+    pub vault_tokens: Account<'info, TokenAccount>,
+    #[account(mut, token::authority = depositor)]
+    pub source: Account<'info, TokenAccount>,
+    pub depositor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: CreatePoolParams)]
+pub struct CreatePool<'info> {
+    #[account(
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        has_one = mint,
+        constraint = pirate.uses_admin_set || pirate.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub pirate: Account<'info, Pirate>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 8 + 1 + 8 + (4 + (8 + 2) * StakePool::MAX_LOCK_BOOSTS) + 2 + (1 + 32) + 16 + 8 + 16 + 8 + 1,
+        seeds = [b"stake_pool", pirate.key().as_ref(), &params.pool_id.to_le_bytes()],
+        bump
+    )]
+    pub pool: Account<'info, StakePool>,
+    pub mint: Account<'info, Mint>,
+    // Staked tokens, held by the Pirate PDA like vault tokens
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"stake_tokens", pool.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = pirate,
+    )]
+    pub stake_tokens: Account<'info, TokenAccount>,
+    // Reward reserve of Funded pools
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"stake_rewards", pool.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = pirate,
+    )]
+    pub reward_tokens: Account<'info, TokenAccount>,
+    // Receives early-unstake penalties, which are burned when omitted
+    #[account(token::mint = mint)]
+    pub penalty_treasury: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
 
 #[derive(Accounts)]
-pub struct InitializePirate<'info> {
+pub struct FundPool<'info> {
+    pub pool: Account<'info, StakePool>,
+    #[account(mut, seeds = [b"stake_rewards", pool.key().as_ref()], bump)]
+    pub reward_tokens: Account<'info, TokenAccount>,
+    #[account(mut, token::authority = funder)]
+    pub source: Account<'info, TokenAccount>,
+    pub funder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    #[account(seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump)]
+    pub pirate: Account<'info, Pirate>,
+    #[account(mut, seeds = [b"stake_pool", pirate.key().as_ref(), &pool.pool_id.to_le_bytes()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
     #[account(
-        init,
-        payer = authority,
-        space = 8 + 32 + 32 + 1 + 8 + 1,
-        seeds = [b"pirate", mint.key().as_ref()],
+        init_if_needed,
+        payer = owner,
+        space = 8 + 32 + 32 + 8 + 16 + 2 + 8 + 16 + 8 + 1,
+        seeds = [b"stake_position", pool.key().as_ref(), from_vault.key().as_ref()],
         bump
     )]
+    pub position: Account<'info, StakePosition>,
+    #[account(constraint = from_vault.owner == owner.key() @ ErrorCode::Unauthorized)]
+    pub from_vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", from_vault.key().as_ref()], bump)]
+    pub from_vault_tokens: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"stake_tokens", pool.key().as_ref()], bump)]
+    pub stake_tokens: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    #[account(mut, seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump, has_one = mint)]
     pub pirate: Account<'info, Pirate>,
+    #[account(mut)]
     pub mint: Account<'info, Mint>,
+    #[account(mut, seeds = [b"stake_pool", pirate.key().as_ref(), &pool.pool_id.to_le_bytes()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(mut, seeds = [b"stake_position", pool.key().as_ref(), vault.key().as_ref()], bump = position.bump)]
+    pub position: Account<'info, StakePosition>,
+    #[account(constraint = vault.owner == owner.key() @ ErrorCode::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", vault.key().as_ref()], bump)]
+    pub vault_tokens: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"stake_tokens", pool.key().as_ref()], bump)]
+    pub stake_tokens: Account<'info, TokenAccount>,
     #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
+    pub penalty_treasury: Option<Account<'info, TokenAccount>>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct MintPirateTokens<'info> {
+pub struct ClaimStakeRewards<'info> {
+    #[account(mut, seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump, has_one = mint)]
+    pub pirate: Account<'info, Pirate>,
     #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    #[account(mut, seeds = [b"stake_pool", pirate.key().as_ref(), &pool.pool_id.to_le_bytes()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(mut, seeds = [b"stake_position", pool.key().as_ref(), vault.key().as_ref()], bump = position.bump)]
+    pub position: Account<'info, StakePosition>,
+    #[account(constraint = vault.owner == owner.key() @ ErrorCode::Unauthorized)]
+    pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", vault.key().as_ref()], bump)]
+    pub vault_tokens: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"stake_rewards", pool.key().as_ref()], bump)]
+    pub reward_tokens: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: CreateVestingParams)]
+pub struct CreateVesting<'info> {
+    #[account(
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        has_one = mint,
+        constraint = pirate.uses_admin_set || pirate.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
     pub pirate: Account<'info, Pirate>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 32 + 8 + 8 + 8 + 8 + (4 + 8 * VestingSchedule::MAX_MILESTONES) + 1 + 8 + 8 + 1 + (1 + 8) + 1,
+        seeds = [b"vesting", pirate.key().as_ref(), beneficiary_vault.key().as_ref(), &params.vesting_id.to_le_bytes()],
+        bump
+    )]
+    pub schedule: Account<'info, VestingSchedule>,
+    pub beneficiary_vault: Account<'info, Vault>,
+    pub mint: Account<'info, Mint>,
+    // Locked grant, held by the Pirate PDA until released
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"vesting_tokens", schedule.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = pirate,
+    )]
+    pub vesting_tokens: Account<'info, TokenAccount>,
+    // Funds the grant
+    #[account(mut, token::authority = authority)]
+    pub source: Account<'info, TokenAccount>,
+    #[account(token::mint = mint)]
+    pub treasury: Account<'info, TokenAccount>,
     #[account(mut)]
-    pub to_vault: Account<'info, Vault>,
     pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct BurnPirateTokens<'info> {
-    #[account(mut)]
+pub struct ReleaseVesting<'info> {
+    #[account(seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump)]
     pub pirate: Account<'info, Pirate>,
-    #[account(mut)]
-    pub from_vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"vesting", pirate.key().as_ref(), beneficiary_vault.key().as_ref(), &schedule.vesting_id.to_le_bytes()],
+        bump = schedule.bump
+    )]
+    pub schedule: Account<'info, VestingSchedule>,
+    pub beneficiary_vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", beneficiary_vault.key().as_ref()], bump)]
+    pub beneficiary_tokens: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"vesting_tokens", schedule.key().as_ref()], bump)]
+    pub vesting_tokens: Account<'info, TokenAccount>,
+    pub cranker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UnlockMilestone<'info> {
+    #[account(
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        constraint = pirate.uses_admin_set || pirate.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub pirate: Account<'info, Pirate>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    #[account(mut, has_one = pirate)]
+    pub schedule: Account<'info, VestingSchedule>,
     pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct TransferPirateTokens<'info> {
-    #[account(mut)]
-    pub from_vault: Account<'info, Vault>,
+pub struct RevokeVesting<'info> {
+    #[account(
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        constraint = pirate.uses_admin_set || pirate.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub pirate: Account<'info, Pirate>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    #[account(mut, has_one = pirate, has_one = treasury @ ErrorCode::VestingTreasuryMismatch)]
+    pub schedule: Account<'info, VestingSchedule>,
+    #[account(mut, seeds = [b"vesting_tokens", schedule.key().as_ref()], bump)]
+    pub vesting_tokens: Account<'info, TokenAccount>,
     #[account(mut)]
-    pub to_vault: Account<'info, Vault>,
+    pub treasury: Account<'info, TokenAccount>,
     pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct Reward<'info> {
-    #[account(mut)]
+#[instruction(params: CreateDistributorParams)]
+pub struct CreateDistributor<'info> {
+    #[account(
+        mut,
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        has_one = mint,
+        constraint = pirate.uses_admin_set || pirate.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
     pub pirate: Account<'info, Pirate>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 32 + 8 + 8 + 8 + 8 + 32 + 1 + (4 + params.num_nodes.div_ceil(8) as usize) + 1,
+        seeds = [b"distributor", pirate.key().as_ref(), &params.distributor_id.to_le_bytes()],
+        bump
+    )]
+    pub distributor: Account<'info, MerkleDistributor>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    // Minted with the full total_amount at creation
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"distributor_tokens", distributor.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = pirate,
+    )]
+    pub distributor_tokens: Account<'info, TokenAccount>,
+    #[account(token::mint = mint)]
+    pub clawback_treasury: Account<'info, TokenAccount>,
     #[account(mut)]
-    pub from_vault: Account<'info, Vault>,
     pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct InitializeVault<'info> {
-    #[account(init, payer = owner, space = 8 + 32 + 8)]
+pub struct ClaimAirdrop<'info> {
+    #[account(seeds = [b"pirate", pirate.mint.as_ref()], bump = pirate.bump)]
+    pub pirate: Account<'info, Pirate>,
+    #[account(
+        mut,
+        seeds = [b"distributor", pirate.key().as_ref(), &distributor.distributor_id.to_le_bytes()],
+        bump = distributor.bump
+    )]
+    pub distributor: Account<'info, MerkleDistributor>,
+    #[account(mut, seeds = [b"distributor_tokens", distributor.key().as_ref()], bump)]
+    pub distributor_tokens: Account<'info, TokenAccount>,
+    #[account(constraint = vault.owner == claimant.key() @ ErrorCode::Unauthorized)]
     pub vault: Account<'info, Vault>,
+    #[account(mut, seeds = [b"vault_tokens", vault.key().as_ref()], bump)]
+    pub vault_tokens: Account<'info, TokenAccount>,
+    pub claimant: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Clawback<'info> {
+    #[account(
+        seeds = [b"pirate", pirate.mint.as_ref()],
+        bump = pirate.bump,
+        constraint = pirate.uses_admin_set || pirate.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub pirate: Account<'info, Pirate>,
+    #[account(seeds = [b"admin_set", pirate.key().as_ref()], bump = admin_set.bump)]
+    pub admin_set: Option<Account<'info, AdminSet>>,
+    #[account(mut, has_one = pirate)]
+    pub proposal: Option<Account<'info, Proposal>>,
+    #[account(mut, has_one = pirate, has_one = clawback_treasury)]
+    pub distributor: Account<'info, MerkleDistributor>,
+    #[account(mut, seeds = [b"distributor_tokens", distributor.key().as_ref()], bump)]
+    pub distributor_tokens: Account<'info, TokenAccount>,
     #[account(mut)]
-    pub owner: Signer<'info>,
-    pub system_program: Program<'info, System>,
+    pub clawback_treasury: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
+// Hands mint authority over to the Pirate PDA so PIRATE can only be minted through this program
 pub fn initialize_pirate(ctx: Context<InitializePirate>) -> Result<()> {
     ctx.accounts.pirate.mint = ctx.accounts.mint.key();
     ctx.accounts.pirate.authority = ctx.accounts.authority.key();
     ctx.accounts.pirate.decimals = ctx.accounts.mint.decimals;
     ctx.accounts.pirate.total_supply = 0;
+    ctx.accounts.pirate.max_supply = None;
+    ctx.accounts.pirate.pending_authority = None;
+    ctx.accounts.pirate.uses_admin_set = false;
     ctx.accounts.pirate.bump = ctx.bumps.pirate;
+    ctx.accounts.roles.pirate = ctx.accounts.pirate.key();
+    ctx.accounts.roles.bump = ctx.bumps.roles;
+
+    let cpi_accounts = SetAuthority {
+        current_authority: ctx.accounts.authority.to_account_info(),
+        account_or_mint: ctx.accounts.mint.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+    token::set_authority(cpi_ctx, AuthorityType::MintTokens, Some(ctx.accounts.pirate.key()))
+}
+
+// First step of an admin handover. Proposing again replaces the pending authority, None cancels it
+pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Option<Pubkey>) -> Result<()> {
+    ctx.accounts.pirate.pending_authority = new_authority;
+    Ok(())
+}
+
+pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+    let pirate = &mut ctx.accounts.pirate;
+    pirate.authority = ctx.accounts.new_authority.key();
+    pirate.pending_authority = None;
+    Ok(())
+}
+
+// Irreversible: from now on sensitive instructions go through proposals, and the set only changes by proposal
+pub fn initialize_admin_set(ctx: Context<InitializeAdminSet>, members: Vec<Pubkey>, threshold: u8) -> Result<()> {
+    validate_admin_set(&members, threshold)?;
+    let admin_set = &mut ctx.accounts.admin_set;
+    admin_set.pirate = ctx.accounts.pirate.key();
+    admin_set.members = members;
+    admin_set.threshold = threshold;
+    admin_set.proposal_count = 0;
+    admin_set.bump = ctx.bumps.admin_set;
+    ctx.accounts.pirate.uses_admin_set = true;
+    Ok(())
+}
+
+pub fn update_admin_set(ctx: Context<UpdateAdminSet>, members: Vec<Pubkey>, threshold: u8) -> Result<()> {
+    validate_admin_set(&members, threshold)?;
+    let args = (&members, threshold).try_to_vec()?;
+    execute_proposal(&ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::UpdateAdminSet, &args)?;
+    let admin_set = &mut ctx.accounts.admin_set;
+    admin_set.members = members;
+    admin_set.threshold = threshold;
+    Ok(())
+}
+
+// The proposer's approval is counted right away
+pub fn create_proposal(ctx: Context<CreateProposal>, action: AdminAction, args_hash: [u8; 32]) -> Result<()> {
+    let admin_set = &mut ctx.accounts.admin_set;
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.pirate = ctx.accounts.pirate.key();
+    proposal.id = admin_set.proposal_count;
+    proposal.action = action;
+    proposal.args_hash = args_hash;
+    proposal.approvals = vec![ctx.accounts.proposer.key()];
+    proposal.executed = false;
+    proposal.bump = ctx.bumps.proposal;
+    admin_set.proposal_count += 1;
+    Ok(())
+}
+
+pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+    let member = ctx.accounts.member.key();
+    let proposal = &mut ctx.accounts.proposal;
+    require!(!proposal.executed && !proposal.approvals.contains(&member), ErrorCode::ProposalAlreadyUsed);
+    require!(proposal.approvals.len() < AdminSet::MAX_MEMBERS, ErrorCode::InvalidAdminSet);
+    proposal.approvals.push(member);
+    Ok(())
+}
+
+// None removes the cap. A cap can be lowered but never below what is already in circulation
+pub fn set_max_supply(ctx: Context<SetMaxSupply>, max_supply: Option<u64>) -> Result<()> {
+    let args = max_supply.try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::SetMaxSupply, &args)?;
+    let pirate = &mut ctx.accounts.pirate;
+    if let Some(max_supply) = max_supply {
+        require!(max_supply >= pirate.total_supply, ErrorCode::InvalidMaxSupply);
+    }
+    pirate.max_supply = max_supply;
+    Ok(())
+}
+
+// Creates or retunes the budget of a reward category. Retuning keeps the current epoch's emitted amount
+pub fn set_emission_budget(
+    ctx: Context<SetEmissionBudget>,
+    category: RewardCategory,
+    epoch_length: i64,
+    budget_per_epoch: u64,
+) -> Result<()> {
+    require!(epoch_length > 0, ErrorCode::InvalidEmissionBudget);
+    let args = (category, epoch_length, budget_per_epoch).try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::SetEmissionBudget, &args)?;
+    let emission_budget = &mut ctx.accounts.emission_budget;
+    if emission_budget.pirate == Pubkey::default() {
+        emission_budget.pirate = ctx.accounts.pirate.key();
+        emission_budget.category = category;
+        emission_budget.epoch_start = Clock::get()?.unix_timestamp;
+        emission_budget.emitted = 0;
+        emission_budget.bump = ctx.bumps.emission_budget;
+    }
+    emission_budget.epoch_length = epoch_length;
+    emission_budget.budget_per_epoch = budget_per_epoch;
+    Ok(())
+}
+
+pub fn add_role(ctx: Context<ManageRole>, role: Role, member: Pubkey) -> Result<()> {
+    let args = (role, member).try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::AddRole, &args)?;
+    let members = ctx.accounts.roles.members_mut(role);
+    require!(!members.contains(&member), ErrorCode::RoleAlreadyGranted);
+    require!(members.len() < RoleConfig::MAX_MEMBERS, ErrorCode::TooManyRoleMembers);
+    members.push(member);
+    Ok(())
+}
+
+pub fn revoke_role(ctx: Context<ManageRole>, role: Role, member: Pubkey) -> Result<()> {
+    let args = (role, member).try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::RevokeRole, &args)?;
+    let members = ctx.accounts.roles.members_mut(role);
+    let index = members.iter().position(|key| *key == member).ok_or(ErrorCode::RoleNotFound)?;
+    members.swap_remove(index);
     Ok(())
 }
 
 pub fn initialize_vault(ctx: Context<InitializeVault>) -> Result<()> {
     ctx.accounts.vault.owner = ctx.accounts.owner.key();
-    ctx.accounts.vault.balance = 0;
+    ctx.accounts.vault.legacy_balance = 0;
     Ok(())
 }
 
-// Mints new Pirate tokens by increasing total_supply and user vault balance.
-pub fn mint_pirate_tokens(ctx: Context<MintPirateTokens>, amount: u64) -> Result<()> {
-    let pirate = &mut ctx.accounts.pirate;
-    let to_vault = &mut ctx.accounts.to_vault;
-    pirate.total_supply = pirate.total_supply.checked_add(amount).ok_or(ErrorCode::Overflow)?;
-    to_vault.balance = to_vault.balance.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+// Approving again replaces the previous allowance rather than adding to it
+pub fn approve(ctx: Context<Approve>, delegate: Pubkey, amount: u64, expires_at: Option<i64>) -> Result<()> {
+    let allowance = &mut ctx.accounts.allowance;
+    allowance.vault = ctx.accounts.vault.key();
+    allowance.delegate = delegate;
+    allowance.amount = amount;
+    allowance.expires_at = expires_at;
+    allowance.bump = ctx.bumps.allowance;
     Ok(())
 }
 
-// Burns Pirate tokens, reducing supply and vault balance.
-pub fn burn_pirate_tokens(ctx: Context<BurnPirateTokens>, amount: u64) -> Result<()> {
-    let pirate = &mut ctx.accounts.pirate;
-    let from_vault = &mut ctx.accounts.from_vault;
-    require!(from_vault.balance >= amount, ErrorCode::InsufficientBalance);
-    pirate.total_supply = pirate.total_supply.checked_sub(amount).ok_or(ErrorCode::Overflow)?;
-    from_vault.balance = from_vault.balance.checked_sub(amount).ok_or(ErrorCode::Overflow)?;
+pub fn revoke(_ctx: Context<Revoke>) -> Result<()> {
     Ok(())
 }
 
-// Transfers Pirate tokens between users' SPL vaults.
+// Closes an empty vault and its token account, refunding both rents to the owner
+pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
+    require!(
+        ctx.accounts.vault_tokens.amount == 0 && ctx.accounts.vault.legacy_balance == 0,
+        ErrorCode::VaultNotEmpty
+    );
+    let pirate = &ctx.accounts.pirate;
+    let bump = [pirate.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"pirate", pirate.mint.as_ref(), &bump]];
+    let cpi_accounts = CloseAccount {
+        account: ctx.accounts.vault_tokens.to_account_info(),
+        destination: ctx.accounts.owner.to_account_info(),
+        authority: pirate.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer_seeds);
+    token::close_account(cpi_ctx)
+}
+
+// Mints new Pirate tokens into a user's vault.
+pub fn mint_pirate_tokens(ctx: Context<MintPirateTokens>, amount: u64) -> Result<()> {
+    let args = (ctx.accounts.to_vault.key(), amount).try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::MintTokens, &args)?;
+    mint_to_vault(&mut ctx.accounts.pirate, &ctx.accounts.mint, &ctx.accounts.to_vault_tokens, &ctx.accounts.token_program, amount)
+}
+
+// Burns Pirate tokens from a vault, reducing supply.
+pub fn burn_pirate_tokens(ctx: Context<BurnPirateTokens>, amount: u64) -> Result<()> {
+    require!(ctx.accounts.from_vault_tokens.amount >= amount, ErrorCode::InsufficientBalance);
+    authorize_spend(&ctx.accounts.from_vault, &ctx.accounts.authority, &mut ctx.accounts.allowance, amount)?;
+    burn_from_vault(&mut ctx.accounts.pirate, &ctx.accounts.mint, &ctx.accounts.from_vault_tokens, &ctx.accounts.token_program, amount)
+}
+
+// Transfers Pirate tokens between users' vaults.
 pub fn transfer_pirate_tokens(ctx: Context<TransferPirateTokens>, amount: u64) -> Result<()> {
-    let from_vault = &mut ctx.accounts.from_vault;
-    let to_vault = &mut ctx.accounts.to_vault;
-    require!(from_vault.balance >= amount, ErrorCode::InsufficientBalance);
-    from_vault.balance = from_vault.balance.checked_sub(amount).ok_or(ErrorCode::Overflow)?;
-    to_vault.balance = to_vault.balance.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    require!(ctx.accounts.from_vault_tokens.amount >= amount, ErrorCode::InsufficientBalance);
+    authorize_spend(&ctx.accounts.from_vault, &ctx.accounts.authority, &mut ctx.accounts.allowance, amount)?;
+    transfer_from_vault(&ctx.accounts.pirate, &ctx.accounts.from_vault_tokens, &ctx.accounts.to_vault_tokens, &ctx.accounts.token_program, amount)
+}
+
+// Moves a vault's legacy ledger balance into real tokens. The ledger was never backed by the mint,
+// so this is a sensitive mint like any other and counts towards total_supply and max_supply
+pub fn migrate_vault(ctx: Context<MigrateVault>) -> Result<()> {
+    let args = ctx.accounts.vault.key().try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::MigrateVault, &args)?;
+    let amount = ctx.accounts.vault.legacy_balance;
+    if amount > 0 {
+        mint_to_vault(&mut ctx.accounts.pirate, &ctx.accounts.mint, &ctx.accounts.vault_tokens, &ctx.accounts.token_program, amount)?;
+    }
+    ctx.accounts.vault.legacy_balance = 0;
+    msg!("Migrated {} PIRATE tokens into vault {}", amount, ctx.accounts.vault.key());
     Ok(())
 }
 
-pub fn reward_level_completion(ctx: Context<Reward>, level: u8) -> Result<()> {
-    let pirate = &mut ctx.accounts.pirate;
-    let from_vault = &mut ctx.accounts.from_vault;
+// Moves tokens out of the vault into any PIRATE token account, e.g. the owner's wallet for marketplace purchases
+pub fn withdraw_pirate_tokens(ctx: Context<WithdrawPirateTokens>, amount: u64) -> Result<()> {
+    require!(ctx.accounts.vault_tokens.amount >= amount, ErrorCode::InsufficientBalance);
+    transfer_from_vault(&ctx.accounts.pirate, &ctx.accounts.vault_tokens, &ctx.accounts.destination, &ctx.accounts.token_program, amount)
+}
 
-    let reward_amount = calculate_level_reward(level)?;
+pub fn deposit_pirate_tokens(ctx: Context<DepositPirateTokens>, amount: u64) -> Result<()> {
+    require!(ctx.accounts.source.amount >= amount, ErrorCode::InsufficientBalance);
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.source.to_account_info(),
+        to: ctx.accounts.vault_tokens.to_account_info(),
+        authority: ctx.accounts.depositor.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+    token::transfer(cpi_ctx, amount)
+}
+
+pub fn reward_level_completion(ctx: Context<LevelReward>, level: u8, nonce: u64, expires_at: i64) -> Result<()> {
+    let attestation = RewardAttestation {
+        pirate: ctx.accounts.pirate.key(),
+        player: ctx.accounts.from_vault.owner,
+        category: RewardCategory::LevelCompletion,
+        reward_id: level as u64,
+        variant: 0,
+        nonce,
+        expires_at,
+    };
+    verify_attestation(&ctx.accounts.instructions, &ctx.accounts.roles, &attestation)?;
+    require!(ctx.accounts.attestation_receipt.player == Pubkey::default(), ErrorCode::AttestationReplayed);
+    claim_receipt(&mut ctx.accounts.attestation_receipt, ctx.accounts.from_vault.owner, ctx.bumps.attestation_receipt)?;
+    let reward_amount = calculate_level_reward(&ctx.accounts.reward_schedule, level)?;
     require!(reward_amount > 0, ErrorCode::InvalidReward);
-    pirate.total_supply = pirate.total_supply.checked_add(reward_amount).ok_or(ErrorCode::Overflow)?;
-    from_vault.balance = from_vault.balance.checked_add(reward_amount).ok_or(ErrorCode::Overflow)?;
+    claim_receipt(&mut ctx.accounts.receipt, ctx.accounts.from_vault.owner, ctx.bumps.receipt)?;
+    consume_emission(&ctx.accounts.emission_budget, reward_amount)?;
+    mint_to_vault(&mut ctx.accounts.pirate, &ctx.accounts.mint, &ctx.accounts.from_vault_tokens, &ctx.accounts.token_program, reward_amount)?;
     msg!("Player rewarded {} PIRATE tokens for completing level {}", reward_amount, level);
     Ok(())
 }
 
-pub fn reward_treasure_found(ctx: Context<Reward>, treasure_type: u8) -> Result<()> {
-    let pirate = &mut ctx.accounts.pirate;
-    let from_vault = &mut ctx.accounts.from_vault;
-    let reward_amount = calculate_treasure_reward(treasure_type)?;
+// treasure_id identifies the individual treasure in the game world, so each one pays out once per player
+pub fn reward_treasure_found(
+    ctx: Context<TreasureReward>,
+    treasure_type: u8,
+    treasure_id: u64,
+    nonce: u64,
+    expires_at: i64,
+) -> Result<()> {
+    let attestation = RewardAttestation {
+        pirate: ctx.accounts.pirate.key(),
+        player: ctx.accounts.from_vault.owner,
+        category: RewardCategory::Treasure,
+        reward_id: treasure_id,
+        variant: treasure_type,
+        nonce,
+        expires_at,
+    };
+    verify_attestation(&ctx.accounts.instructions, &ctx.accounts.roles, &attestation)?;
+    require!(ctx.accounts.attestation_receipt.player == Pubkey::default(), ErrorCode::AttestationReplayed);
+    claim_receipt(&mut ctx.accounts.attestation_receipt, ctx.accounts.from_vault.owner, ctx.bumps.attestation_receipt)?;
+    let reward_amount = calculate_treasure_reward(&ctx.accounts.reward_schedule, treasure_type)?;
     require!(reward_amount > 0, ErrorCode::InvalidReward);
-    pirate.total_supply = pirate.total_supply.checked_add(reward_amount).ok_or(ErrorCode::Overflow)?;
-    from_vault.balance = from_vault.balance.checked_add(reward_amount).ok_or(ErrorCode::Overflow)?;
-    msg!("Player rewarded {} PIRATE tokens for finding treasure", reward_amount);
+    claim_receipt(&mut ctx.accounts.receipt, ctx.accounts.from_vault.owner, ctx.bumps.receipt)?;
+    consume_emission(&ctx.accounts.emission_budget, reward_amount)?;
+    mint_to_vault(&mut ctx.accounts.pirate, &ctx.accounts.mint, &ctx.accounts.from_vault_tokens, &ctx.accounts.token_program, reward_amount)?;
+    msg!("Player rewarded {} PIRATE tokens for finding treasure {}", reward_amount, treasure_id);
     Ok(())
 }
 
-pub fn daily_login_bonus(ctx: Context<Reward>) -> Result<()> {
-    let pirate = &mut ctx.accounts.pirate;
-    let from_vault = &mut ctx.accounts.from_vault;
-    let bonus_amount = 55_u64;
-    pirate.total_supply = pirate.total_supply.checked_add(bonus_amount).ok_or(ErrorCode::Overflow)?;
-    from_vault.balance = from_vault.balance.checked_add(bonus_amount).ok_or(ErrorCode::Overflow)?;
+pub fn initialize_reward_schedule(ctx: Context<InitializeRewardSchedule>, params: RewardScheduleParams) -> Result<()> {
+    let args = params.try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::InitializeRewardSchedule, &args)?;
+    let reward_schedule = &mut ctx.accounts.reward_schedule;
+    reward_schedule.pirate = ctx.accounts.pirate.key();
+    reward_schedule.bump = ctx.bumps.reward_schedule;
+    apply_reward_schedule(reward_schedule, params)
+}
+
+pub fn update_reward_schedule(ctx: Context<UpdateRewardSchedule>, params: RewardScheduleParams) -> Result<()> {
+    let args = params.try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::UpdateRewardSchedule, &args)?;
+    apply_reward_schedule(&mut ctx.accounts.reward_schedule, params)
+}
+
+pub fn initialize_login_bonus(ctx: Context<InitializeLoginBonus>, schedule: Vec<u64>, max_streak: u16) -> Result<()> {
+    validate_login_schedule(&schedule, max_streak)?;
+    let args = (&schedule, max_streak).try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::InitializeLoginBonus, &args)?;
+    let login_bonus = &mut ctx.accounts.login_bonus;
+    login_bonus.pirate = ctx.accounts.pirate.key();
+    login_bonus.schedule = schedule;
+    login_bonus.max_streak = max_streak;
+    login_bonus.bump = ctx.bumps.login_bonus;
+    Ok(())
+}
+
+pub fn update_login_bonus(ctx: Context<UpdateLoginBonus>, schedule: Vec<u64>, max_streak: u16) -> Result<()> {
+    validate_login_schedule(&schedule, max_streak)?;
+    let args = (&schedule, max_streak).try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::UpdateLoginBonus, &args)?;
+    let login_bonus = &mut ctx.accounts.login_bonus;
+    login_bonus.schedule = schedule;
+    login_bonus.max_streak = max_streak;
+    Ok(())
+}
+
+// Claimable once every 24h. Claiming within 48h of the last claim extends the streak, later resets it
+pub fn daily_login_bonus(ctx: Context<DailyLogin>, nonce: u64, expires_at: i64) -> Result<()> {
+    let attestation = RewardAttestation {
+        pirate: ctx.accounts.pirate.key(),
+        player: ctx.accounts.from_vault.owner,
+        category: RewardCategory::DailyLogin,
+        reward_id: 0,
+        variant: 0,
+        nonce,
+        expires_at,
+    };
+    verify_attestation(&ctx.accounts.instructions, &ctx.accounts.roles, &attestation)?;
+    require!(ctx.accounts.attestation_receipt.player == Pubkey::default(), ErrorCode::AttestationReplayed);
+    claim_receipt(&mut ctx.accounts.attestation_receipt, ctx.accounts.from_vault.owner, ctx.bumps.attestation_receipt)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let tracker = &mut ctx.accounts.login_tracker;
+    if tracker.player == Pubkey::default() {
+        tracker.player = ctx.accounts.from_vault.owner;
+        tracker.bump = ctx.bumps.login_tracker;
+    } else {
+        require!(now >= tracker.last_claim + SECONDS_PER_DAY, ErrorCode::LoginBonusNotReady);
+    }
+
+    let max_streak = ctx.accounts.login_bonus.max_streak;
+    tracker.streak = if tracker.streak > 0 && now < tracker.last_claim + 2 * SECONDS_PER_DAY {
+        tracker.streak.saturating_add(1).min(max_streak)
+    } else {
+        1
+    };
+    tracker.last_claim = now;
+
+    let streak = tracker.streak;
+    let bonus_amount = ctx.accounts.login_bonus.bonus_for(streak);
+    consume_emission(&ctx.accounts.emission_budget, bonus_amount)?;
+    mint_to_vault(&mut ctx.accounts.pirate, &ctx.accounts.mint, &ctx.accounts.from_vault_tokens, &ctx.accounts.token_program, bonus_amount)?;
+    msg!("Player rewarded {} PIRATE tokens for a {} day login streak", bonus_amount, streak);
+    Ok(())
+}
+
+pub fn create_pool(ctx: Context<CreatePool>, params: CreatePoolParams) -> Result<()> {
+    // The penalty destination is part of what the admin set approves
+    let penalty_treasury = ctx.accounts.penalty_treasury.as_ref().map(|treasury| treasury.key());
+    let args = (&params, penalty_treasury).try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::CreatePool, &args)?;
+    require!(
+        params.lock_period >= 0
+            && params.early_unstake_penalty_bps <= 10_000
+            && params.lock_boosts.len() <= StakePool::MAX_LOCK_BOOSTS
+            && params.lock_boosts.iter().all(|boost| boost.duration >= 0),
+        ErrorCode::InvalidStakePool
+    );
+    let pool = &mut ctx.accounts.pool;
+    pool.pirate = ctx.accounts.pirate.key();
+    pool.pool_id = params.pool_id;
+    pool.reward_rate = params.reward_rate;
+    pool.reward_source = params.reward_source;
+    pool.lock_period = params.lock_period;
+    pool.lock_boosts = params.lock_boosts;
+    pool.early_unstake_penalty_bps = params.early_unstake_penalty_bps;
+    pool.penalty_treasury = penalty_treasury;
+    pool.acc_reward_per_weight = 0;
+    pool.total_staked = 0;
+    pool.total_weight = 0;
+    pool.last_update = Clock::get()?.unix_timestamp;
+    pool.bump = ctx.bumps.pool;
+    Ok(())
+}
+
+// Tops up the reward reserve of a Funded pool
+pub fn fund_pool(ctx: Context<FundPool>, amount: u64) -> Result<()> {
+    require!(ctx.accounts.source.amount >= amount, ErrorCode::InsufficientBalance);
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.source.to_account_info(),
+        to: ctx.accounts.reward_tokens.to_account_info(),
+        authority: ctx.accounts.funder.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+    token::transfer(cpi_ctx, amount)
+}
+
+// Staking again extends the lock and can move the whole position to a bigger boost, never a smaller one
+pub fn stake(ctx: Context<Stake>, amount: u64, boost_index: Option<u8>) -> Result<()> {
+    require!(amount > 0 && ctx.accounts.from_vault_tokens.amount >= amount, ErrorCode::InsufficientBalance);
+    let now = Clock::get()?.unix_timestamp;
+    let pool = &mut ctx.accounts.pool;
+    let position = &mut ctx.accounts.position;
+    let (lock_duration, boost_bps) = match boost_index {
+        Some(index) => {
+            let boost = pool.lock_boosts.get(index as usize).ok_or(ErrorCode::InvalidLockBoost)?;
+            (boost.duration.max(pool.lock_period), boost.boost_bps)
+        }
+        None => (pool.lock_period, 0),
+    };
+    if position.owner == Pubkey::default() {
+        position.owner = ctx.accounts.owner.key();
+        position.vault = ctx.accounts.from_vault.key();
+        position.bump = ctx.bumps.position;
+    }
+    require!(position.amount == 0 || boost_bps >= position.boost_bps, ErrorCode::LockBoostDowngrade);
+
+    update_pool(pool, now)?;
+    settle_position(pool, position)?;
+    position.amount = position.amount.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    position.boost_bps = boost_bps;
+    position.lock_until = position.lock_until.max(now.checked_add(lock_duration).ok_or(ErrorCode::Overflow)?);
+    set_position_weight(pool, position)?;
+    pool.total_staked = pool.total_staked.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+
+    transfer_from_vault(&ctx.accounts.pirate, &ctx.accounts.from_vault_tokens, &ctx.accounts.stake_tokens, &ctx.accounts.token_program, amount)
+}
+
+// Unstaking before lock_until forfeits early_unstake_penalty_bps of the amount, burned or sent to the pool's treasury
+pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let pool = &mut ctx.accounts.pool;
+    let position = &mut ctx.accounts.position;
+    require!(amount > 0 && amount <= position.amount, ErrorCode::InsufficientBalance);
+
+    update_pool(pool, now)?;
+    settle_position(pool, position)?;
+    position.amount -= amount;
+    set_position_weight(pool, position)?;
+    pool.total_staked -= amount;
+
+    let penalty = if now < position.lock_until {
+        (amount as u128 * pool.early_unstake_penalty_bps as u128 / 10_000) as u64
+    } else {
+        0
+    };
+    let penalty_treasury = pool.penalty_treasury;
+
+    transfer_from_vault(&ctx.accounts.pirate, &ctx.accounts.stake_tokens, &ctx.accounts.vault_tokens, &ctx.accounts.token_program, amount - penalty)?;
+    if penalty > 0 {
+        match penalty_treasury {
+            Some(treasury) => {
+                let treasury_tokens = ctx.accounts.penalty_treasury.as_ref()
+                    .filter(|account| account.key() == treasury)
+                    .ok_or(ErrorCode::InvalidPenaltyTreasury)?;
+                transfer_from_vault(&ctx.accounts.pirate, &ctx.accounts.stake_tokens, treasury_tokens, &ctx.accounts.token_program, penalty)?;
+            }
+            None => burn_from_vault(&mut ctx.accounts.pirate, &ctx.accounts.mint, &ctx.accounts.stake_tokens, &ctx.accounts.token_program, penalty)?,
+        }
+        msg!("Early unstake penalty of {} PIRATE tokens", penalty);
+    }
+    Ok(())
+}
+
+pub fn claim_rewards(ctx: Context<ClaimStakeRewards>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let pool = &mut ctx.accounts.pool;
+    let position = &mut ctx.accounts.position;
+    update_pool(pool, now)?;
+    settle_position(pool, position)?;
+    let rewards = position.pending_rewards;
+    require!(rewards > 0, ErrorCode::NoRewardsToClaim);
+    position.pending_rewards = 0;
+
+    match pool.reward_source {
+        RewardSource::Mint => {
+            mint_to_vault(&mut ctx.accounts.pirate, &ctx.accounts.mint, &ctx.accounts.vault_tokens, &ctx.accounts.token_program, rewards)?
+        }
+        RewardSource::Funded => {
+            require!(ctx.accounts.reward_tokens.amount >= rewards, ErrorCode::InsufficientBalance);
+            transfer_from_vault(&ctx.accounts.pirate, &ctx.accounts.reward_tokens, &ctx.accounts.vault_tokens, &ctx.accounts.token_program, rewards)?
+        }
+    }
+    msg!("Player claimed {} PIRATE staking rewards", rewards);
+    Ok(())
+}
+
+pub fn create_vesting(ctx: Context<CreateVesting>, params: CreateVestingParams) -> Result<()> {
+    let args = (&params, ctx.accounts.beneficiary_vault.key(), ctx.accounts.treasury.key()).try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::CreateVesting, &args)?;
+    let total_amount = params.milestones.iter()
+        .try_fold(params.linear_amount, |total, amount| total.checked_add(*amount))
+        .ok_or(ErrorCode::Overflow)?;
+    require!(
+        params.start_ts <= params.cliff_ts
+            && params.cliff_ts <= params.end_ts
+            && params.milestones.len() <= VestingSchedule::MAX_MILESTONES
+            && total_amount > 0,
+        ErrorCode::InvalidVestingSchedule
+    );
+    require!(ctx.accounts.source.amount >= total_amount, ErrorCode::InsufficientBalance);
+
+    let schedule = &mut ctx.accounts.schedule;
+    schedule.pirate = ctx.accounts.pirate.key();
+    schedule.beneficiary_vault = ctx.accounts.beneficiary_vault.key();
+    schedule.vesting_id = params.vesting_id;
+    schedule.treasury = ctx.accounts.treasury.key();
+    schedule.start_ts = params.start_ts;
+    schedule.cliff_ts = params.cliff_ts;
+    schedule.end_ts = params.end_ts;
+    schedule.linear_amount = params.linear_amount;
+    schedule.milestones = params.milestones;
+    schedule.unlocked_milestones = 0;
+    schedule.total_amount = total_amount;
+    schedule.released = 0;
+    schedule.revocable = params.revocable;
+    schedule.revoked_at = None;
+    schedule.bump = ctx.bumps.schedule;
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.source.to_account_info(),
+        to: ctx.accounts.vesting_tokens.to_account_info(),
+        authority: ctx.accounts.authority.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+    token::transfer(cpi_ctx, total_amount)
+}
+
+// Permissionless, vested tokens always go to the beneficiary's vault
+pub fn release(ctx: Context<ReleaseVesting>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let schedule = &mut ctx.accounts.schedule;
+    let releasable = schedule.vested_amount(now).saturating_sub(schedule.released);
+    require!(releasable > 0, ErrorCode::NothingToRelease);
+    schedule.released += releasable;
+
+    transfer_from_vault(&ctx.accounts.pirate, &ctx.accounts.vesting_tokens, &ctx.accounts.beneficiary_tokens, &ctx.accounts.token_program, releasable)?;
+    msg!("Released {} vested PIRATE tokens", releasable);
+    Ok(())
+}
+
+pub fn unlock_milestone(ctx: Context<UnlockMilestone>, index: u8) -> Result<()> {
+    let args = (ctx.accounts.schedule.key(), index).try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::UnlockMilestone, &args)?;
+    let schedule = &mut ctx.accounts.schedule;
+    require!(schedule.revoked_at.is_none(), ErrorCode::VestingRevoked);
+    require!(
+        (index as usize) < schedule.milestones.len() && schedule.unlocked_milestones & (1 << index) == 0,
+        ErrorCode::InvalidMilestone
+    );
+    schedule.unlocked_milestones |= 1 << index;
+    Ok(())
+}
+
+// Stops vesting now and returns everything not yet vested to the treasury. Vested tokens stay releasable
+pub fn revoke_vesting(ctx: Context<RevokeVesting>) -> Result<()> {
+    let args = ctx.accounts.schedule.key().try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::RevokeVesting, &args)?;
+    let now = Clock::get()?.unix_timestamp;
+    let schedule = &mut ctx.accounts.schedule;
+    require!(schedule.revocable, ErrorCode::VestingNotRevocable);
+    require!(schedule.revoked_at.is_none(), ErrorCode::VestingRevoked);
+    let unvested = schedule.total_amount - schedule.vested_amount(now);
+    schedule.revoked_at = Some(now);
+
+    if unvested > 0 {
+        transfer_from_vault(&ctx.accounts.pirate, &ctx.accounts.vesting_tokens, &ctx.accounts.treasury, &ctx.accounts.token_program, unvested)?;
+    }
+    msg!("Revoked {} unvested PIRATE tokens", unvested);
+    Ok(())
+}
+
+// Publishes a season's Merkle root and mints its total into the distributor
+pub fn create_distributor(ctx: Context<CreateDistributor>, params: CreateDistributorParams) -> Result<()> {
+    let args = (&params, ctx.accounts.clawback_treasury.key()).try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::CreateDistributor, &args)?;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        params.total_amount > 0
+            && params.num_nodes > 0
+            && params.num_nodes <= MerkleDistributor::MAX_NODES
+            && params.clawback_ts > now,
+        ErrorCode::InvalidDistributor
+    );
+    let distributor = &mut ctx.accounts.distributor;
+    distributor.pirate = ctx.accounts.pirate.key();
+    distributor.distributor_id = params.distributor_id;
+    distributor.root = params.root;
+    distributor.total_amount = params.total_amount;
+    distributor.total_claimed = 0;
+    distributor.num_nodes = params.num_nodes;
+    distributor.clawback_ts = params.clawback_ts;
+    distributor.clawback_treasury = ctx.accounts.clawback_treasury.key();
+    distributor.clawed_back = false;
+    distributor.claimed = vec![0; params.num_nodes.div_ceil(8) as usize];
+    distributor.bump = ctx.bumps.distributor;
+
+    mint_to_vault(&mut ctx.accounts.pirate, &ctx.accounts.mint, &ctx.accounts.distributor_tokens, &ctx.accounts.token_program, params.total_amount)
+}
+
+pub fn claim(ctx: Context<ClaimAirdrop>, index: u64, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+    let distributor = &mut ctx.accounts.distributor;
+    require!(!distributor.clawed_back, ErrorCode::DistributorClawedBack);
+    require!(index < distributor.num_nodes, ErrorCode::InvalidMerkleProof);
+    require!(!distributor.is_claimed(index), ErrorCode::AirdropAlreadyClaimed);
+
+    let leaf = keccak::hashv(&[&index.to_le_bytes(), ctx.accounts.claimant.key().as_ref(), &amount.to_le_bytes()]).0;
+    require!(verify_merkle_proof(&proof, distributor.root, leaf), ErrorCode::InvalidMerkleProof);
+
+    distributor.set_claimed(index);
+    distributor.total_claimed = distributor.total_claimed.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    require!(distributor.total_claimed <= distributor.total_amount, ErrorCode::DistributorExhausted);
+
+    transfer_from_vault(&ctx.accounts.pirate, &ctx.accounts.distributor_tokens, &ctx.accounts.vault_tokens, &ctx.accounts.token_program, amount)?;
+    msg!("Airdrop of {} PIRATE tokens claimed for leaf {}", amount, index);
+    Ok(())
+}
+
+// After the deadline everything still unclaimed goes to the treasury and further claims fail
+pub fn clawback(ctx: Context<Clawback>) -> Result<()> {
+    let args = ctx.accounts.distributor.key().try_to_vec()?;
+    authorize_sensitive(&ctx.accounts.pirate, &ctx.accounts.admin_set, &mut ctx.accounts.proposal, AdminAction::Clawback, &args)?;
+    let distributor = &mut ctx.accounts.distributor;
+    require!(Clock::get()?.unix_timestamp >= distributor.clawback_ts, ErrorCode::ClawbackNotStarted);
+    require!(!distributor.clawed_back, ErrorCode::DistributorClawedBack);
+    distributor.clawed_back = true;
+
+    let remaining = ctx.accounts.distributor_tokens.amount;
+    if remaining > 0 {
+        transfer_from_vault(&ctx.accounts.pirate, &ctx.accounts.distributor_tokens, &ctx.accounts.clawback_treasury, &ctx.accounts.token_program, remaining)?;
+    }
+    msg!("Clawed back {} unclaimed PIRATE tokens", remaining);
     Ok(())
 }
 
 // Helper functions
-fn calculate_level_reward(level: u8) -> Result<u64> {
-    Ok(level as u64 * 10)
+fn validate_admin_set(members: &[Pubkey], threshold: u8) -> Result<()> {
+    let unique = members.iter().enumerate().all(|(index, member)| !members[..index].contains(member));
+    require!(
+        !members.is_empty() && members.len() <= AdminSet::MAX_MEMBERS && unique && threshold >= 1 && threshold as usize <= members.len(),
+        ErrorCode::InvalidAdminSet
+    );
+    Ok(())
+}
+
+// Without an admin set the account constraints already checked the single authority
+fn authorize_sensitive(
+    pirate: &Pirate,
+    admin_set: &Option<Account<AdminSet>>,
+    proposal: &mut Option<Account<Proposal>>,
+    action: AdminAction,
+    args: &[u8],
+) -> Result<()> {
+    if !pirate.uses_admin_set {
+        return Ok(());
+    }
+    match (admin_set, proposal) {
+        (Some(admin_set), Some(proposal)) => execute_proposal(admin_set, proposal, action, args),
+        _ => err!(ErrorCode::ProposalRequired),
+    }
+}
+
+// Only approvals from current members count, so removed members can't push old proposals through
+fn execute_proposal(admin_set: &AdminSet, proposal: &mut Proposal, action: AdminAction, args: &[u8]) -> Result<()> {
+    require!(!proposal.executed, ErrorCode::ProposalAlreadyUsed);
+    require!(
+        proposal.action == action && proposal.args_hash == keccak::hash(args).0,
+        ErrorCode::ProposalMismatch
+    );
+    let approvals = proposal.approvals.iter().filter(|member| admin_set.members.contains(member)).count();
+    require!(approvals >= admin_set.threshold as usize, ErrorCode::ProposalNotApproved);
+    proposal.executed = true;
+    Ok(())
+}
+
+// Pairs are hashed in sorted order, so proofs need no left/right flags
+fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            keccak::hashv(&[&node, sibling]).0
+        } else {
+            keccak::hashv(&[sibling, &node]).0
+        }
+    });
+    computed == root
+}
+
+// Accrues reward_rate for the time since the last update, spread over the pool's current weight
+fn update_pool(pool: &mut StakePool, now: i64) -> Result<()> {
+    if now > pool.last_update && pool.total_weight > 0 {
+        let accrued = ((now - pool.last_update) as u128)
+            .checked_mul(pool.reward_rate as u128)
+            .and_then(|rewards| rewards.checked_mul(StakePool::REWARD_PRECISION))
+            .ok_or(ErrorCode::Overflow)?;
+        pool.acc_reward_per_weight = pool.acc_reward_per_weight
+            .checked_add(accrued / pool.total_weight)
+            .ok_or(ErrorCode::Overflow)?;
+    }
+    pool.last_update = pool.last_update.max(now);
+    Ok(())
+}
+
+// Moves rewards earned since the last settlement into pending_rewards
+fn settle_position(pool: &StakePool, position: &mut StakePosition) -> Result<()> {
+    let accumulated = position.weight.checked_mul(pool.acc_reward_per_weight).ok_or(ErrorCode::Overflow)?;
+    let earned = accumulated.checked_sub(position.reward_debt).ok_or(ErrorCode::Overflow)? / StakePool::REWARD_PRECISION;
+    let earned = u64::try_from(earned).map_err(|_| ErrorCode::Overflow)?;
+    position.pending_rewards = position.pending_rewards.checked_add(earned).ok_or(ErrorCode::Overflow)?;
+    position.reward_debt = accumulated;
+    Ok(())
+}
+
+// Call after settle_position whenever amount or boost changes
+fn set_position_weight(pool: &mut StakePool, position: &mut StakePosition) -> Result<()> {
+    if position.amount == 0 {
+        position.boost_bps = 0;
+    }
+    let weight = StakePool::weight_for(position.amount, position.boost_bps);
+    pool.total_weight = (pool.total_weight - position.weight).checked_add(weight).ok_or(ErrorCode::Overflow)?;
+    position.weight = weight;
+    position.reward_debt = weight.checked_mul(pool.acc_reward_per_weight).ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+fn validate_login_schedule(schedule: &[u64], max_streak: u16) -> Result<()> {
+    require!(
+        !schedule.is_empty() && schedule.len() <= LoginBonusConfig::MAX_SCHEDULE_DAYS && max_streak > 0,
+        ErrorCode::InvalidLoginSchedule
+    );
+    Ok(())
 }
 
-fn calculate_treasure_reward(_treasure_type: u8) -> Result<u64> {
-    Ok(100)
-}
\ No newline at end of file
+fn calculate_level_reward(schedule: &RewardSchedule, level: u8) -> Result<u64> {
+    require!(level > 0 && level <= schedule.max_level, ErrorCode::InvalidLevel);
+    if !schedule.level_table.is_empty() {
+        return schedule.level_table.get(level as usize - 1).copied().ok_or(error!(ErrorCode::InvalidLevel));
+    }
+    schedule.level_step
+        .checked_mul(level as u64)
+        .and_then(|amount| amount.checked_add(schedule.level_base))
+        .ok_or(error!(ErrorCode::Overflow))
+}
+
+fn calculate_treasure_reward(schedule: &RewardSchedule, treasure_type: u8) -> Result<u64> {
+    let disabled = treasure_type < 16 && schedule.disabled_treasures & (1 << treasure_type) != 0;
+    require!(!disabled, ErrorCode::InvalidTreasure);
+    schedule.treasure_rewards.get(treasure_type as usize).copied().ok_or(error!(ErrorCode::InvalidTreasure))
+}
+
+fn apply_reward_schedule(schedule: &mut RewardSchedule, params: RewardScheduleParams) -> Result<()> {
+    require!(
+        params.level_table.len() <= RewardSchedule::MAX_LEVELS
+            && params.treasure_rewards.len() <= RewardSchedule::MAX_TREASURE_TYPES,
+        ErrorCode::InvalidRewardSchedule
+    );
+    // A table has to price every level up to max_level, or claims fail later
+    require!(
+        params.level_table.is_empty() || params.level_table.len() == params.max_level as usize,
+        ErrorCode::InvalidRewardSchedule
+    );
+    schedule.level_base = params.level_base;
+    schedule.level_step = params.level_step;
+    schedule.level_table = params.level_table;
+    schedule.max_level = params.max_level;
+    schedule.treasure_rewards = params.treasure_rewards;
+    schedule.disabled_treasures = params.disabled_treasures;
+    Ok(())
+}
+
+// The vault owner can always spend, anyone else needs an unexpired allowance that covers the amount
+fn authorize_spend(
+    vault: &Account<Vault>,
+    authority: &Signer,
+    allowance: &mut Option<Account<Allowance>>,
+    amount: u64,
+) -> Result<()> {
+    if vault.owner == authority.key() {
+        return Ok(());
+    }
+    let allowance = allowance.as_mut().ok_or(ErrorCode::Unauthorized)?;
+    if let Some(expires_at) = allowance.expires_at {
+        require!(Clock::get()?.unix_timestamp < expires_at, ErrorCode::AllowanceExpired);
+    }
+    allowance.amount = allowance.amount.checked_sub(amount).ok_or(ErrorCode::AllowanceExceeded)?;
+    Ok(())
+}
+
+// Starts a new epoch once the current one has elapsed, keeping epoch boundaries aligned to the first epoch_start.
+// The budget PDA is always passed; a category is uncapped only while its budget account does not exist
+fn consume_emission(budget_info: &AccountInfo, amount: u64) -> Result<()> {
+    if budget_info.data_is_empty() {
+        return Ok(());
+    }
+    require_keys_eq!(*budget_info.owner, crate::ID, anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram);
+    let mut budget = EmissionBudget::try_deserialize(&mut &budget_info.try_borrow_data()?[..])?;
+    let now = Clock::get()?.unix_timestamp;
+    let epoch_end = budget.epoch_start.checked_add(budget.epoch_length).ok_or(ErrorCode::Overflow)?;
+    if now >= epoch_end {
+        budget.epoch_start = now - (now - budget.epoch_start) % budget.epoch_length;
+        budget.emitted = 0;
+    }
+    let emitted = budget.emitted.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    require!(emitted <= budget.budget_per_epoch, ErrorCode::EmissionBudgetExhausted);
+    budget.emitted = emitted;
+    budget.try_serialize(&mut &mut budget_info.try_borrow_mut_data()?[..])
+}
+
+// The instruction right before the reward must be an ed25519 verification of `expected`, with the
+// signature, key and message all inside that instruction, signed by a registered game server
+fn verify_attestation(instructions: &AccountInfo, roles: &RoleConfig, expected: &RewardAttestation) -> Result<()> {
+    require!(Clock::get()?.unix_timestamp <= expected.expires_at, ErrorCode::AttestationExpired);
+
+    let current_index = load_current_index_checked(instructions)?;
+    require!(current_index > 0, ErrorCode::MissingAttestation);
+    let ed25519_ix = load_instruction_at_checked(current_index as usize - 1, instructions)?;
+    require!(ed25519_ix.program_id == ed25519_program::ID, ErrorCode::MissingAttestation);
+
+    // Layout: [count u8, padding u8, offsets: 7 x u16], see the ed25519 program's Ed25519SignatureOffsets
+    let data = &ed25519_ix.data;
+    require!(data.len() >= 16 && data[0] == 1, ErrorCode::MissingAttestation);
+    let offset = |index: usize| u16::from_le_bytes([data[2 + 2 * index], data[3 + 2 * index]]);
+    let (signature_ix, public_key_offset, public_key_ix) = (offset(1), offset(2) as usize, offset(3));
+    let (message_offset, message_size, message_ix) = (offset(4) as usize, offset(5) as usize, offset(6));
+    require!(
+        signature_ix == u16::MAX && public_key_ix == u16::MAX && message_ix == u16::MAX,
+        ErrorCode::MissingAttestation
+    );
+
+    let server = data.get(public_key_offset..public_key_offset + 32).ok_or(ErrorCode::MissingAttestation)?;
+    let server = Pubkey::try_from(server).map_err(|_| ErrorCode::MissingAttestation)?;
+    require!(roles.has_role(Role::GameServer, &server), ErrorCode::UnknownGameServer);
+
+    let message = data.get(message_offset..message_offset + message_size).ok_or(ErrorCode::MissingAttestation)?;
+    require!(message == expected.try_to_vec()?.as_slice(), ErrorCode::AttestationMismatch);
+    Ok(())
+}
+
+// A receipt created by this call has no player yet, an existing one means the reward was paid before
+fn claim_receipt(receipt: &mut Account<ClaimReceipt>, player: Pubkey, bump: u8) -> Result<()> {
+    require!(receipt.player == Pubkey::default(), ErrorCode::RewardAlreadyClaimed);
+    receipt.player = player;
+    receipt.claimed_at = Clock::get()?.unix_timestamp;
+    receipt.bump = bump;
+    Ok(())
+}
+
+// Mints with the Pirate PDA as mint authority and tracks the supply issued by the program
+fn mint_to_vault<'info>(
+    pirate: &mut Account<'info, Pirate>,
+    mint: &Account<'info, Mint>,
+    vault_tokens: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    pirate.total_supply = pirate.total_supply.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    if let Some(max_supply) = pirate.max_supply {
+        require!(pirate.total_supply <= max_supply, ErrorCode::MaxSupplyExceeded);
+    }
+
+    let bump = [pirate.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"pirate", pirate.mint.as_ref(), &bump]];
+    let cpi_accounts = MintTo {
+        mint: mint.to_account_info(),
+        to: vault_tokens.to_account_info(),
+        authority: pirate.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds);
+    token::mint_to(cpi_ctx, amount)
+}
+
+// Burns tokens held by the Pirate PDA and removes them from total_supply
+fn burn_from_vault<'info>(
+    pirate: &mut Account<'info, Pirate>,
+    mint: &Account<'info, Mint>,
+    vault_tokens: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let bump = [pirate.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"pirate", pirate.mint.as_ref(), &bump]];
+    let cpi_accounts = Burn {
+        mint: mint.to_account_info(),
+        from: vault_tokens.to_account_info(),
+        authority: pirate.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds);
+    token::burn(cpi_ctx, amount)?;
+
+    pirate.total_supply = pirate.total_supply.checked_sub(amount).ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// Vault token accounts are owned by the Pirate PDA, so every move out of a vault is signed with its seeds
+fn transfer_from_vault<'info>(
+    pirate: &Account<'info, Pirate>,
+    from: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let bump = [pirate.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[b"pirate", pirate.mint.as_ref(), &bump]];
+    let cpi_accounts = Transfer {
+        from: from.to_account_info(),
+        to: to.to_account_info(),
+        authority: pirate.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds);
+    token::transfer(cpi_ctx, amount)
+}
@@ -18,10 +18,92 @@ pub mod token_economy {
         instructions::initialize_pirate(ctx)
     }
 
+    // Two-step admin handover: the current admin proposes, the new one accepts
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Option<Pubkey>) -> Result<()> {
+        instructions::propose_authority(ctx, new_authority)
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        instructions::accept_authority(ctx)
+    }
+
+    // Switch sensitive instructions to M-of-N approval, admin only
+    pub fn initialize_admin_set(
+        ctx: Context<InitializeAdminSet>,
+        members: Vec<Pubkey>,
+        threshold: u8
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::initialize_admin_set(ctx, members, threshold)
+    }
+
+    pub fn update_admin_set(
+        ctx: Context<UpdateAdminSet>,
+        members: Vec<Pubkey>,
+        threshold: u8
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::update_admin_set(ctx, members, threshold)
+    }
+
+    // Admin set members propose and approve sensitive instruction calls
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        action: AdminAction,
+        args_hash: [u8; 32]
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::create_proposal(ctx, action, args_hash)
+    }
+
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> anchor_lang::prelude::Result<()> {
+        instructions::approve_proposal(ctx)
+    }
+
+    // Cap the total PIRATE supply, or lift the cap with None, admin only
+    pub fn set_max_supply(ctx: Context<SetMaxSupply>, max_supply: Option<u64>) -> Result<()> {
+        instructions::set_max_supply(ctx, max_supply)
+    }
+
+    // Per-epoch mint budget of a reward category, admin only
+    pub fn set_emission_budget(
+        ctx: Context<SetEmissionBudget>,
+        category: RewardCategory,
+        epoch_length: i64,
+        budget_per_epoch: u64
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::set_emission_budget(ctx, category, epoch_length, budget_per_epoch)
+    }
+
+    // Grant or revoke minter / reward signer roles, admin only
+    pub fn add_role(ctx: Context<ManageRole>, role: Role, member: Pubkey) -> Result<()> {
+        instructions::add_role(ctx, role, member)
+    }
+
+    pub fn revoke_role(ctx: Context<ManageRole>, role: Role, member: Pubkey) -> Result<()> {
+        instructions::revoke_role(ctx, role, member)
+    }
+
     pub fn initialize_vault(ctx: Context<InitializeVault>) -> Result<()> {
         instructions::initialize_vault(ctx)
     }
 
+    // Let a delegate spend from a vault up to an allowance, optionally until expires_at
+    pub fn approve(
+        ctx: Context<Approve>,
+        delegate: Pubkey,
+        amount: u64,
+        expires_at: Option<i64>
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::approve(ctx, delegate, amount, expires_at)
+    }
+
+    pub fn revoke(ctx: Context<Revoke>) -> anchor_lang::prelude::Result<()> {
+        instructions::revoke(ctx)
+    }
+
+    // Close an empty vault and reclaim its rent
+    pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
+        instructions::close_vault(ctx)
+    }
+
     // Mint tokens to a player's vault
     pub fn mint_pirate_tokens(
         ctx: Context<MintPirateTokens>, 
@@ -30,7 +112,7 @@ pub mod token_economy {
         instructions::mint_pirate_tokens(ctx, amount)
     }
 
-    // Burn tokens from a player's vault
+    // Burn tokens from a player's vault, by the owner or a delegate
     pub fn burn_pirate_tokens(
         ctx: Context<BurnPirateTokens>, 
         amount: u64
@@ -38,7 +120,7 @@ pub mod token_economy {
         instructions::burn_pirate_tokens(ctx, amount)
     }
 
-    // Transfer tokens between vaults (wallets)
+    // Transfer tokens between vaults (wallets), by the owner or a delegate
     pub fn transfer_pirate_tokens(
         ctx: Context<TransferPirateTokens>, 
         amount: u64
@@ -46,24 +128,163 @@ pub mod token_economy {
         instructions::transfer_pirate_tokens(ctx, amount)
     }
 
-    // Reward for level completion
+    // Mint a pre-SPL vault balance into real tokens
+    pub fn migrate_vault(ctx: Context<MigrateVault>) -> anchor_lang::prelude::Result<()> {
+        instructions::migrate_vault(ctx)
+    }
+
+    // Move tokens from a vault to a wallet's token account
+    pub fn withdraw_pirate_tokens(
+        ctx: Context<WithdrawPirateTokens>, 
+        amount: u64
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::withdraw_pirate_tokens(ctx, amount)
+    }
+
+    // Move tokens from a wallet's token account into a vault
+    pub fn deposit_pirate_tokens(
+        ctx: Context<DepositPirateTokens>, 
+        amount: u64
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::deposit_pirate_tokens(ctx, amount)
+    }
+
+    // Reward for level completion. Rewards need a game server attestation in the preceding ed25519 instruction
     pub fn reward_level_completion(
-        ctx: Context<Reward>, 
-        level: u8
+        ctx: Context<LevelReward>, 
+        level: u8,
+        nonce: u64,
+        expires_at: i64
     ) -> anchor_lang::prelude::Result<()> {
-        instructions::reward_level_completion(ctx, level)
+        instructions::reward_level_completion(ctx, level, nonce, expires_at)
     }
 
     // Reward for treasure finding
     pub fn reward_treasure_found(
-        ctx: Context<Reward>, 
-        treasure_type: u8
+        ctx: Context<TreasureReward>, 
+        treasure_type: u8,
+        treasure_id: u64,
+        nonce: u64,
+        expires_at: i64
     ) -> anchor_lang::prelude::Result<()> {
-        instructions::reward_treasure_found(ctx, treasure_type)
+        instructions::reward_treasure_found(ctx, treasure_type, treasure_id, nonce, expires_at)
+    }
+
+    // Level and treasure reward amounts, admin only
+    pub fn initialize_reward_schedule(
+        ctx: Context<InitializeRewardSchedule>,
+        params: RewardScheduleParams
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::initialize_reward_schedule(ctx, params)
+    }
+
+    pub fn update_reward_schedule(
+        ctx: Context<UpdateRewardSchedule>,
+        params: RewardScheduleParams
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::update_reward_schedule(ctx, params)
     }
 
     // Daily login bonus
-    pub fn reward_daily_login(ctx: Context<Reward>) -> anchor_lang::prelude::Result<()> {
-        instructions::daily_login_bonus(ctx)
+    pub fn reward_daily_login(
+        ctx: Context<DailyLogin>,
+        nonce: u64,
+        expires_at: i64
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::daily_login_bonus(ctx, nonce, expires_at)
+    }
+
+    // Streak bonus schedule, admin only
+    pub fn initialize_login_bonus(
+        ctx: Context<InitializeLoginBonus>,
+        schedule: Vec<u64>,
+        max_streak: u16
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::initialize_login_bonus(ctx, schedule, max_streak)
+    }
+
+    pub fn update_login_bonus(
+        ctx: Context<UpdateLoginBonus>,
+        schedule: Vec<u64>,
+        max_streak: u16
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::update_login_bonus(ctx, schedule, max_streak)
+    }
+
+    // Staking pool with a per-second reward rate, admin only
+    pub fn create_pool(
+        ctx: Context<CreatePool>,
+        params: CreatePoolParams
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::create_pool(ctx, params)
+    }
+
+    // Add PIRATE to a Funded pool's reward reserve
+    pub fn fund_pool(ctx: Context<FundPool>, amount: u64) -> anchor_lang::prelude::Result<()> {
+        instructions::fund_pool(ctx, amount)
+    }
+
+    // Stake tokens from a vault, optionally locking longer for a boost
+    pub fn stake(
+        ctx: Context<Stake>,
+        amount: u64,
+        boost_index: Option<u8>
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::stake(ctx, amount, boost_index)
+    }
+
+    // Unstake back into the vault, with a penalty while still locked
+    pub fn unstake(ctx: Context<Unstake>, amount: u64) -> anchor_lang::prelude::Result<()> {
+        instructions::unstake(ctx, amount)
+    }
+
+    // Pay accrued staking rewards into the vault
+    pub fn claim_rewards(ctx: Context<ClaimStakeRewards>) -> anchor_lang::prelude::Result<()> {
+        instructions::claim_rewards(ctx)
+    }
+
+    // Lock a PIRATE grant under a cliff, linear and milestone schedule, admin only
+    pub fn create_vesting(
+        ctx: Context<CreateVesting>,
+        params: CreateVestingParams
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::create_vesting(ctx, params)
+    }
+
+    // Release vested tokens to the beneficiary, anyone can crank
+    pub fn release(ctx: Context<ReleaseVesting>) -> anchor_lang::prelude::Result<()> {
+        instructions::release(ctx)
+    }
+
+    pub fn unlock_milestone(ctx: Context<UnlockMilestone>, index: u8) -> anchor_lang::prelude::Result<()> {
+        instructions::unlock_milestone(ctx, index)
+    }
+
+    // Return unvested tokens to the treasury, admin only
+    pub fn revoke_vesting(ctx: Context<RevokeVesting>) -> anchor_lang::prelude::Result<()> {
+        instructions::revoke_vesting(ctx)
+    }
+
+    // Publish a season airdrop Merkle root and mint its total, admin only
+    pub fn create_distributor(
+        ctx: Context<CreateDistributor>,
+        params: CreateDistributorParams
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::create_distributor(ctx, params)
+    }
+
+    // Claim an airdrop leaf into the claimant's vault
+    pub fn claim(
+        ctx: Context<ClaimAirdrop>,
+        index: u64,
+        amount: u64,
+        proof: Vec<[u8; 32]>
+    ) -> anchor_lang::prelude::Result<()> {
+        instructions::claim(ctx, index, amount, proof)
+    }
+
+    // Send unclaimed airdrop tokens to the treasury after the deadline, admin only
+    pub fn clawback(ctx: Context<Clawback>) -> anchor_lang::prelude::Result<()> {
+        instructions::clawback(ctx)
     }
 }
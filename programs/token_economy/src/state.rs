@@ -10,11 +10,359 @@ pub struct Pirate {
     pub decimals : u8,
     pub total_supply : u64,
     pub bump :u8,
+    pub max_supply : Option<u64>, // Hard cap on total_supply, unlimited when None
+    pub pending_authority : Option<Pubkey>, // Proposed admin, takes over once it accepts
+    pub uses_admin_set : bool,              // Sensitive instructions then need an approved Proposal
 }
 
 
 #[account]
 pub struct Vault {
     pub owner : Pubkey,
-    pub balance : u64,
+    pub legacy_balance : u64, // Unbacked balance from before PIRATE was an SPL token, cleared by migrate_vault
+}
+
+
+// Who may mint and who may sign gameplay rewards. The admin is Pirate.authority
+#[account]
+pub struct RoleConfig {
+    pub pirate : Pubkey,
+    pub minters : Vec<Pubkey>,
+    pub reward_signers : Vec<Pubkey>,
+    pub game_servers : Vec<Pubkey>, // ed25519 keys whose attestations back rewards
+    pub bump : u8,
+}
+
+impl RoleConfig {
+    pub const MAX_MEMBERS: usize = 8;
+
+    pub fn members(&self, role: Role) -> &Vec<Pubkey> {
+        match role {
+            Role::Minter => &self.minters,
+            Role::RewardSigner => &self.reward_signers,
+            Role::GameServer => &self.game_servers,
+        }
+    }
+
+    pub fn members_mut(&mut self, role: Role) -> &mut Vec<Pubkey> {
+        match role {
+            Role::Minter => &mut self.minters,
+            Role::RewardSigner => &mut self.reward_signers,
+            Role::GameServer => &mut self.game_servers,
+        }
+    }
+
+    pub fn has_role(&self, role: Role, key: &Pubkey) -> bool {
+        self.members(role).contains(key)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Minter,
+    RewardSigner,
+    GameServer,
+}
+
+// Streak bonus schedule: day N of a streak pays schedule[N - 1], the last entry repeats for longer streaks
+#[account]
+pub struct LoginBonusConfig {
+    pub pirate : Pubkey,
+    pub schedule : Vec<u64>,
+    pub max_streak : u16,
+    pub bump : u8,
+}
+
+impl LoginBonusConfig {
+    pub const MAX_SCHEDULE_DAYS: usize = 30;
+
+    pub fn bonus_for(&self, streak: u16) -> u64 {
+        let day = (streak.max(1) as usize).min(self.schedule.len());
+        self.schedule[day - 1]
+    }
+}
+
+#[account]
+pub struct LoginTracker {
+    pub player : Pubkey,
+    pub last_claim : i64,
+    pub streak : u16,
+    pub bump : u8,
+}
+
+// Admin-tunable reward amounts. Levels pay from level_table when it is set, otherwise level_base + level_step * level
+#[account]
+pub struct RewardSchedule {
+    pub pirate : Pubkey,
+    pub level_base : u64,
+    pub level_step : u64,
+    pub level_table : Vec<u64>,
+    pub max_level : u8,
+    pub treasure_rewards : Vec<u64>, // Indexed by treasure type
+    pub disabled_treasures : u16,    // Bit N disables treasure type N
+    pub bump : u8,
+}
+
+impl RewardSchedule {
+    pub const MAX_LEVELS: usize = 100;
+    pub const MAX_TREASURE_TYPES: usize = 16;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RewardScheduleParams {
+    pub level_base : u64,
+    pub level_step : u64,
+    pub level_table : Vec<u64>,
+    pub max_level : u8,
+    pub treasure_rewards : Vec<u64>,
+    pub disabled_treasures : u16,
+}
+
+// Marks a level or treasure as paid out to a player
+#[account]
+pub struct ClaimReceipt {
+    pub player : Pubkey,
+    pub claimed_at : i64,
+    pub bump : u8,
+}
+
+// Message a game server signs in the ed25519 instruction preceding a reward, borsh encoded
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RewardAttestation {
+    pub pirate : Pubkey,
+    pub player : Pubkey,
+    pub category : RewardCategory,
+    pub reward_id : u64, // Level, treasure id, or 0 for daily logins
+    pub variant : u8,    // Treasure type, otherwise 0
+    pub nonce : u64,
+    pub expires_at : i64,
+}
+
+// Caps how much a reward category can mint per epoch. The epoch rolls over lazily on the next claim
+#[account]
+pub struct EmissionBudget {
+    pub pirate : Pubkey,
+    pub category : RewardCategory,
+    pub epoch_length : i64,
+    pub budget_per_epoch : u64,
+    pub epoch_start : i64,
+    pub emitted : u64, // Minted in the current epoch
+    pub bump : u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RewardCategory {
+    LevelCompletion,
+    Treasure,
+    DailyLogin,
+}
+
+// Rewards accrue at reward_rate per second, shared by stake weight through acc_reward_per_weight
+#[account]
+pub struct StakePool {
+    pub pirate : Pubkey,
+    pub pool_id : u64,
+    pub reward_rate : u64,
+    pub reward_source : RewardSource,
+    pub lock_period : i64,
+    pub lock_boosts : Vec<LockBoost>,
+    pub early_unstake_penalty_bps : u16,
+    pub penalty_treasury : Option<Pubkey>, // Penalties are burned when None
+    pub acc_reward_per_weight : u128,      // Scaled by REWARD_PRECISION
+    pub total_staked : u64,
+    pub total_weight : u128,
+    pub last_update : i64,
+    pub bump : u8,
+}
+
+impl StakePool {
+    pub const MAX_LOCK_BOOSTS: usize = 4;
+    pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+    pub fn weight_for(amount: u64, boost_bps: u16) -> u128 {
+        amount as u128 * (10_000 + boost_bps as u128) / 10_000
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RewardSource {
+    Mint,   // Minted on claim, counts towards max_supply
+    Funded, // Paid from the pool's stake_rewards account
+}
+
+// Locking for longer than the pool's lock_period in exchange for extra stake weight
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct LockBoost {
+    pub duration : i64,
+    pub boost_bps : u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CreatePoolParams {
+    pub pool_id : u64,
+    pub reward_rate : u64,
+    pub reward_source : RewardSource,
+    pub lock_period : i64,
+    pub lock_boosts : Vec<LockBoost>,
+    pub early_unstake_penalty_bps : u16,
+}
+
+#[account]
+pub struct StakePosition {
+    pub owner : Pubkey,
+    pub vault : Pubkey,
+    pub amount : u64,
+    pub weight : u128,
+    pub boost_bps : u16,
+    pub lock_until : i64,
+    pub reward_debt : u128, // weight * acc_reward_per_weight at the last settlement
+    pub pending_rewards : u64,
+    pub bump : u8,
+}
+
+// PIRATE granted over time: linear_amount unlocks from cliff_ts to end_ts (measured from start_ts), milestones unlock when the admin marks them
+#[account]
+pub struct VestingSchedule {
+    pub pirate : Pubkey,
+    pub beneficiary_vault : Pubkey,
+    pub vesting_id : u64,
+    pub treasury : Pubkey, // Receives unvested tokens on revoke
+    pub start_ts : i64,
+    pub cliff_ts : i64,
+    pub end_ts : i64,
+    pub linear_amount : u64,
+    pub milestones : Vec<u64>,
+    pub unlocked_milestones : u8, // Bit N unlocks milestones[N]
+    pub total_amount : u64,
+    pub released : u64,
+    pub revocable : bool,
+    pub revoked_at : Option<i64>,
+    pub bump : u8,
+}
+
+impl VestingSchedule {
+    pub const MAX_MILESTONES: usize = 8;
+
+    // Vesting stops at revoked_at
+    pub fn vested_amount(&self, now: i64) -> u64 {
+        let now = self.revoked_at.map_or(now, |revoked_at| now.min(revoked_at));
+        let linear = if now < self.cliff_ts {
+            0
+        } else if now >= self.end_ts {
+            self.linear_amount
+        } else {
+            (self.linear_amount as u128 * (now - self.start_ts) as u128 / (self.end_ts - self.start_ts) as u128) as u64
+        };
+        let milestones: u64 = self.milestones.iter().enumerate()
+            .filter(|(index, _)| self.unlocked_milestones & (1 << index) != 0)
+            .map(|(_, amount)| amount)
+            .sum();
+        linear + milestones
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CreateVestingParams {
+    pub vesting_id : u64,
+    pub start_ts : i64,
+    pub cliff_ts : i64,
+    pub end_ts : i64,
+    pub linear_amount : u64,
+    pub milestones : Vec<u64>,
+    pub revocable : bool,
+}
+
+// Lets a delegate spend up to amount from a vault until expires_at
+#[account]
+pub struct Allowance {
+    pub vault : Pubkey,
+    pub delegate : Pubkey,
+    pub amount : u64,
+    pub expires_at : Option<i64>,
+    pub bump : u8,
+}
+
+// Season airdrop: leaf i is keccak(i as u64 le || claimant || amount as u64 le), nodes hash their sorted children
+#[account]
+pub struct MerkleDistributor {
+    pub pirate : Pubkey,
+    pub distributor_id : u64,
+    pub root : [u8; 32],
+    pub total_amount : u64,
+    pub total_claimed : u64,
+    pub num_nodes : u64,
+    pub clawback_ts : i64,
+    pub clawback_treasury : Pubkey,
+    pub clawed_back : bool,
+    pub claimed : Vec<u8>, // Bit i is set once leaf i has been claimed
+    pub bump : u8,
+}
+
+impl MerkleDistributor {
+    pub const MAX_NODES: u64 = 65_536;
+
+    pub fn is_claimed(&self, index: u64) -> bool {
+        self.claimed[(index / 8) as usize] & (1 << (index % 8)) != 0
+    }
+
+    pub fn set_claimed(&mut self, index: u64) {
+        self.claimed[(index / 8) as usize] |= 1 << (index % 8);
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CreateDistributorParams {
+    pub distributor_id : u64,
+    pub root : [u8; 32],
+    pub total_amount : u64,
+    pub num_nodes : u64,
+    pub clawback_ts : i64,
+}
+
+// Optional M-of-N admins. Once created, minting and schedule changes need `threshold` member approvals
+#[account]
+pub struct AdminSet {
+    pub pirate : Pubkey,
+    pub members : Vec<Pubkey>,
+    pub threshold : u8,
+    pub proposal_count : u64,
+    pub bump : u8,
+}
+
+impl AdminSet {
+    pub const MAX_MEMBERS: usize = 10;
+}
+
+// Approval of one sensitive instruction call. args_hash is keccak of the instruction's borsh encoded arguments,
+// followed by the keys of any accounts it pays out to (pool, vesting and distributor treasuries)
+#[account]
+pub struct Proposal {
+    pub pirate : Pubkey,
+    pub id : u64,
+    pub action : AdminAction,
+    pub args_hash : [u8; 32],
+    pub approvals : Vec<Pubkey>,
+    pub executed : bool,
+    pub bump : u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AdminAction {
+    MintTokens,           // (to_vault, amount)
+    SetMaxSupply,         // (max_supply)
+    SetEmissionBudget,    // (category, epoch_length, budget_per_epoch)
+    UpdateRewardSchedule, // (params)
+    UpdateLoginBonus,     // (schedule, max_streak)
+    UpdateAdminSet,       // (members, threshold)
+    MigrateVault,         // (vault)
+    AddRole,              // (role, member)
+    RevokeRole,           // (role, member)
+    InitializeRewardSchedule, // (params)
+    InitializeLoginBonus, // (schedule, max_streak)
+    CreatePool,           // (params, penalty_treasury)
+    CreateVesting,        // (params, beneficiary_vault, treasury)
+    UnlockMilestone,      // (schedule, index)
+    RevokeVesting,        // (schedule)
+    CreateDistributor,    // (params, clawback_treasury)
+    Clawback,             // (distributor)
 }
\ No newline at end of file
@@ -43,21 +43,198 @@ describe("fighter_registry", () => {
     expect(fighterAccount.level).to.equal(1);
   });
 
-  it("Updates the Fighter", async () => {
+  const gameServer = anchor.web3.Keypair.generate();
+  const [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("registry_config")],
+    program.programId
+  );
+
+  const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+
+  it("Only lets the upgrade authority create the registry config", async () => {
+    const stranger = anchor.web3.Keypair.generate();
+    const airdrop = await provider.connection.requestAirdrop(stranger.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+    await provider.connection.confirmTransaction(airdrop);
+    try {
+      await program.methods
+        .initializeConfig()
+        .accounts({
+          config: configPda,
+          admin: stranger.publicKey,
+          program: program.programId,
+          programData,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([stranger])
+        .rpc();
+      expect.fail("Should have failed");
+    } catch (error: any) {
+      expect(error.toString()).to.include("UnauthorizedAdmin");
+    }
+  });
+
+  it("Registers a game server", async () => {
     await program.methods
-      .updatefighter(98, 45, 123, 3, new anchor.BN(0))
+      .initializeConfig()
       .accounts({
-        fighter: fighterPda,
-        authority: user.publicKey,
+        config: configPda,
+        admin: user.publicKey,
+        program: program.programId,
+        programData,
+        systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
 
-    const updated = await program.account.fighter.fetch(fighterPda);
+    await program.methods
+      .addGameServer(gameServer.publicKey)
+      .accounts({ config: configPda, admin: user.publicKey })
+      .rpc();
+
+    const config = await program.account.registryConfig.fetch(configPda);
+    expect(config.gameServers.map((k) => k.toBase58())).to.include(gameServer.publicKey.toBase58());
+  });
+
+  it("Rejects progression from the fighter's own authority", async () => {
+    try {
+      await program.methods
+        .grantExperience(10000)
+        .accounts({ fighter: fighterPda, config: configPda, gameServer: user.publicKey })
+        .rpc();
+      throw new Error("Self-granted experience should fail");
+    } catch (err) {
+      expect(err.message).to.include("UnauthorizedServer");
+    }
+  });
+
+  it("Derives level from granted experience", async () => {
+    await program.methods
+      .grantExperience(450)
+      .accounts({ fighter: fighterPda, config: configPda, gameServer: gameServer.publicKey })
+      .signers([gameServer])
+      .rpc();
+
+    const fighter = await program.account.fighter.fetch(fighterPda);
+    expect(fighter.experience).to.equal(450);
+    expect(fighter.level).to.equal(3);
+  });
+
+  it("Applies damage and heals up to max health", async () => {
+    await program.methods
+      .applyDamage(30)
+      .accounts({ fighter: fighterPda, config: configPda, gameServer: gameServer.publicKey })
+      .signers([gameServer])
+      .rpc();
+    expect((await program.account.fighter.fetch(fighterPda)).health).to.equal(70);
+
+    await program.methods
+      .heal(100)
+      .accounts({ fighter: fighterPda, config: configPda, gameServer: gameServer.publicKey })
+      .signers([gameServer])
+      .rpc();
+    // Level 3 fighters cap at 110 health
+    expect((await program.account.fighter.fetch(fighterPda)).health).to.equal(110);
+  });
+
+  it("Consumes stamina but not beyond zero", async () => {
+    await program.methods
+      .consumeStamina(40)
+      .accounts({ fighter: fighterPda, config: configPda, gameServer: gameServer.publicKey })
+      .signers([gameServer])
+      .rpc();
+    expect((await program.account.fighter.fetch(fighterPda)).stamina).to.equal(60);
+
+    try {
+      await program.methods
+        .consumeStamina(100)
+        .accounts({ fighter: fighterPda, config: configPda, gameServer: gameServer.publicKey })
+        .signers([gameServer])
+        .rpc();
+      throw new Error("Overspending stamina should fail");
+    } catch (err) {
+      expect(err.message).to.include("InsufficientStamina");
+    }
+  });
+
+  it("Keeps later islands locked until the previous boss is beaten", async () => {
+    try {
+      await program.methods
+        .travelToIsland(2)
+        .accounts({ fighter: fighterPda, authority: user.publicKey })
+        .rpc();
+      throw new Error("Travel to a locked island should fail");
+    } catch (err) {
+      expect(err.message).to.include("IslandLocked");
+    }
+
+    // Level 5 and the island 1 boss open island 2
+    await program.methods
+      .grantExperience(1150)
+      .accounts({ fighter: fighterPda, config: configPda, gameServer: gameServer.publicKey })
+      .signers([gameServer])
+      .rpc();
+    await program.methods
+      .recordBossDefeat(1)
+      .accounts({ fighter: fighterPda, config: configPda, gameServer: gameServer.publicKey })
+      .signers([gameServer])
+      .rpc();
+
+    const fighter = await program.account.fighter.fetch(fighterPda);
+    expect(fighter.level).to.equal(5);
+    expect(fighter.unlockedIslands).to.equal(0b11);
+  });
+
+  it("Travels to an unlocked island for stamina, then waits out the cooldown", async () => {
+    await program.methods
+      .travelToIsland(2)
+      .accounts({ fighter: fighterPda, authority: user.publicKey })
+      .rpc();
+
+    const fighter = await program.account.fighter.fetch(fighterPda);
+    expect(fighter.currentIsland).to.equal(2);
+    expect(fighter.stamina).to.equal(40);
+
+    try {
+      await program.methods
+        .travelToIsland(1)
+        .accounts({ fighter: fighterPda, authority: user.publicKey })
+        .rpc();
+      throw new Error("Travel during cooldown should fail");
+    } catch (err) {
+      expect(err.message).to.include("TravelCooldown");
+    }
+  });
+
+  it("Views regenerated values without writing them", async () => {
+    const view = await program.methods
+      .viewFighter()
+      .accounts({ fighter: fighterPda })
+      .view();
+
+    // Level 5 maximums; regeneration only ever adds on top of the stored values
+    expect(view.maxHealth).to.equal(120);
+    expect(view.maxStamina).to.equal(108);
+    expect(view.health).to.be.at.least(110);
+    expect(view.stamina).to.be.at.least(40);
+    expect(view.currentIsland).to.equal(2);
+  });
 
-    expect(updated.health).to.equal(98);
-    expect(updated.stamina).to.equal(45);
-    expect(updated.experience).to.equal(123);
-    expect(updated.level).to.equal(3);
+  it("Leaves fighters already on the current layout alone", async () => {
+    try {
+      await program.methods
+        .migrateFighter()
+        .accounts({
+          fighter: fighterPda,
+          authority: user.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+      expect.fail("Should have failed");
+    } catch (err) {
+      expect(err.toString()).to.include("AlreadyMigrated");
+    }
   });
 
   it("Deletes the Fighter", async () => {
@@ -0,0 +1,1326 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Marketplace } from "../target/types/marketplace";
+import { NftItems } from "../target/types/nft_items";
+import { FighterRegistry } from "../target/types/fighter_registry";
+import { expect } from "chai";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_CLOCK_PUBKEY,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
+
+describe("marketplace", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Marketplace as Program<Marketplace>;
+  const nftProgram = anchor.workspace.NftItems as Program<NftItems>;
+  const fighterProgram = anchor.workspace.FighterRegistry as Program<FighterRegistry>;
+  const wallet = provider.wallet as anchor.Wallet;
+  const connection = provider.connection;
+
+  const MPL_CORE_PROGRAM_ID = new PublicKey("CoREENxT6tW1HoK8ypY1SxRMZTcVPm7R94rH4PZNhX7d");
+  // Listings priced in SOL use the System Program id as their payment mint
+  const NATIVE_SOL = SystemProgram.programId;
+  const FEE_BPS = 250;
+
+  const seller = Keypair.generate();
+  const buyer = Keypair.generate();
+  const feeTreasury = Keypair.generate();
+
+  let paymentMint: PublicKey;
+  let sellerPayment: PublicKey;
+  let buyerPayment: PublicKey;
+  let treasuryPayment: PublicKey;
+  let coreCollection: PublicKey;
+
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("marketplace_config")],
+    program.programId
+  );
+  const [itemCollectionPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("collection")],
+    nftProgram.programId
+  );
+
+  const pda = (...seeds: Buffer[]) => PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+  const listingPda = (mint: PublicKey) => pda(Buffer.from("listing"), mint.toBuffer());
+  const gameItemPda = (asset: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("game_item"), asset.toBuffer()], nftProgram.programId)[0];
+
+  const fighterPda = (owner: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("fighter"), owner.toBuffer()], fighterProgram.programId)[0];
+
+  const expectError = async (tx: Promise<unknown>, code: string) => {
+    try {
+      await tx;
+      expect.fail(`Expected ${code}`);
+    } catch (err) {
+      expect(err.toString()).to.include(code);
+    }
+  };
+
+  // Anchor events a confirmed transaction logged
+  const eventsOf = async (signature: string) => {
+    const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    const parser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+    return [...parser.parseLogs(tx.meta.logMessages)];
+  };
+
+  const tokenBalance = async (account: PublicKey) => Number((await getAccount(connection, account)).amount);
+
+  // On-chain unix timestamp, read from the Clock sysvar
+  const chainTime = async () => Number((await connection.getAccountInfo(SYSVAR_CLOCK_PUBKEY)).data.readBigInt64LE(32));
+
+  const waitForChainTime = async (timestamp: number) => {
+    while ((await chainTime()) < timestamp) {
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  const airdrop = async (to: PublicKey, sol: number) => {
+    const sig = await connection.requestAirdrop(to, sol * anchor.web3.LAMPORTS_PER_SOL);
+    await connection.confirmTransaction(sig);
+  };
+
+  // Core game item minted through nft-items into the collection its own suite created
+  const mintCoreItem = async (owner: PublicKey, itemType: object = { weapon: {} }, rarity = 3) => {
+    const asset = Keypair.generate();
+    await nftProgram.methods
+      .mintGameItem("Cutlass", "https://example.com/cutlass.json", itemType as any, rarity, {
+        attackPower: 40,
+        defense: 5,
+        speedBoost: 0,
+        specialAbility: 0,
+      })
+      .accounts({
+        collection: itemCollectionPda,
+        collectionMint: coreCollection,
+        gameItem: gameItemPda(asset.publicKey),
+        asset: asset.publicKey,
+        payer: wallet.publicKey,
+        owner,
+        mplCoreProgram: MPL_CORE_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([asset])
+      .rpc();
+    return asset.publicKey;
+  };
+
+  // Mints a 0-decimal SPL NFT to the owner's associated token account
+  const mintSplNft = async (owner: PublicKey) => {
+    const mint = await createMint(connection, wallet.payer, wallet.publicKey, null, 0);
+    const account = await getOrCreateAssociatedTokenAccount(connection, wallet.payer, mint, owner);
+    await mintTo(connection, wallet.payer, mint, account.address, wallet.payer, 1);
+    return { mint, account: account.address };
+  };
+
+  const tokenAccount = async (mint: PublicKey, owner: PublicKey) =>
+    (await getOrCreateAssociatedTokenAccount(connection, wallet.payer, mint, owner)).address;
+
+  const writable = (pubkey: PublicKey) => ({ pubkey, isSigner: false, isWritable: true });
+  const readonly = (pubkey: PublicKey) => ({ pubkey, isSigner: false, isWritable: false });
+
+  const escrowPda = (mint: PublicKey) => pda(Buffer.from("escrow"), listingPda(mint).toBuffer());
+
+  // Escrows the seller's SPL NFT under a fixed-price listing
+  const listNft = (
+    nft: { mint: PublicKey; account: PublicKey },
+    price: number,
+    mint = paymentMint,
+    expiresAt: anchor.BN | null = null
+  ) =>
+    program.methods
+      .listNft(new anchor.BN(price), mint, expiresAt)
+      .accounts({
+        listing: listingPda(nft.mint),
+        escrowNftToken: escrowPda(nft.mint),
+        seller: seller.publicKey,
+        sellerNftToken: nft.account,
+        config: configPda,
+        nftMint: nft.mint,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([seller]);
+
+  // SOL listings are paid from the buyer's wallet into the seller and treasury wallets
+  const buyNftAccounts = (nftMint: PublicKey, buyerNftToken: PublicKey, sol = false) => ({
+    listing: listingPda(nftMint),
+    escrowNftToken: escrowPda(nftMint),
+    buyer: buyer.publicKey,
+    buyerPaymentToken: sol ? null : buyerPayment,
+    seller: seller.publicKey,
+    sellerPayment: sol ? seller.publicKey : sellerPayment,
+    buyerNftToken,
+    config: configPda,
+    feeTreasury: sol ? feeTreasury.publicKey : treasuryPayment,
+    nftMint,
+    priceHistory: null,
+    tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+    systemProgram: SystemProgram.programId,
+  });
+
+  const initializePriceHistory = async (mint: PublicKey, payment = paymentMint) => {
+    const priceHistory = pda(Buffer.from("price_history"), mint.toBuffer(), payment.toBuffer());
+    await program.methods
+      .initializePriceHistory()
+      .accounts({
+        priceHistory,
+        mint,
+        paymentMint: payment,
+        payer: wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    return priceHistory;
+  };
+
+  // The wallet created the nft-items collection, so it can put a Royalties plugin on it through Core directly
+  // (AddCollectionPluginV1 / RemoveCollectionPluginV1, optional accounts passed as the Core program id)
+  const setCollectionRoyalties = async (creator: PublicKey | null, basisPoints = 0) => {
+    const data = creator
+      ? Buffer.concat([
+          Buffer.from([3, 0]), // AddCollectionPluginV1, Plugin::Royalties
+          new anchor.BN(basisPoints).toArrayLike(Buffer, "le", 2),
+          new anchor.BN(1).toArrayLike(Buffer, "le", 4),
+          creator.toBuffer(),
+          Buffer.from([100, 0, 0]), // 100% share, RuleSet::None, default authority
+        ])
+      : Buffer.from([5, 0]); // RemoveCollectionPluginV1, PluginType::Royalties
+    const ix = new TransactionInstruction({
+      programId: MPL_CORE_PROGRAM_ID,
+      keys: [
+        { pubkey: coreCollection, isSigner: false, isWritable: true },
+        { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
+        { pubkey: wallet.publicKey, isSigner: true, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: MPL_CORE_PROGRAM_ID, isSigner: false, isWritable: false },
+      ],
+      data,
+    });
+    await provider.sendAndConfirm(new Transaction().add(ix));
+  };
+
+  const assetOwner = async (asset: PublicKey) =>
+    new PublicKey((await connection.getAccountInfo(asset)).data.subarray(1, 33));
+
+  before(async () => {
+    await Promise.all([seller, buyer, feeTreasury].map((k) => airdrop(k.publicKey, 10)));
+
+    paymentMint = await createMint(connection, wallet.payer, wallet.publicKey, null, 6);
+    const ata = async (owner: PublicKey, mint = paymentMint) =>
+      (await getOrCreateAssociatedTokenAccount(connection, wallet.payer, mint, owner)).address;
+    sellerPayment = await ata(seller.publicKey);
+    buyerPayment = await ata(buyer.publicKey);
+    treasuryPayment = await ata(feeTreasury.publicKey);
+    await mintTo(connection, wallet.payer, paymentMint, buyerPayment, wallet.payer, 1_000_000_000);
+
+    // The nft-items suite runs first and leaves its Core collection as the update authority of every item
+    const [item] = await nftProgram.account.gameItem.all();
+    const assetData = (await connection.getAccountInfo(item.account.asset)).data;
+    expect(assetData[33]).to.equal(2); // UpdateAuthority::Collection
+    coreCollection = new PublicKey(assetData.subarray(34, 66));
+  });
+
+  // Runs before the nested suites, which all need the config
+  it("Only lets the upgrade authority initialize the marketplace", async () => {
+    const initialize = (admin: Keypair) =>
+      program.methods
+        .initializeMarketplace(FEE_BPS, [paymentMint, NATIVE_SOL])
+        .accounts({
+          config: configPda,
+          admin: admin.publicKey,
+          feeTreasury: feeTreasury.publicKey,
+          program: program.programId,
+          programData: PublicKey.findProgramAddressSync(
+            [program.programId.toBuffer()],
+            new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+          )[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+    await expectError(initialize(seller), "NotMarketplaceAdmin");
+    await initialize(wallet.payer);
+
+    const config = await program.account.marketplaceConfig.fetch(configPda);
+    expect(config.admin.toBase58()).to.equal(wallet.publicKey.toBase58());
+  });
+
+  describe("Core listings", () => {
+    let asset: PublicKey;
+
+    const coreAccounts = () => ({
+      listing: listingPda(asset),
+      asset,
+      collection: coreCollection,
+      gameItem: gameItemPda(asset),
+      config: configPda,
+      nftItemsProgram: nftProgram.programId,
+      mplCoreProgram: MPL_CORE_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+
+    const listCore = (price: number) =>
+      program.methods
+        .listCoreAsset(new anchor.BN(price), paymentMint, null)
+        .accounts({ ...coreAccounts(), seller: seller.publicKey })
+        .signers([seller])
+        .rpc({ commitment: "confirmed" });
+
+    const buyCore = (fee = treasuryPayment) =>
+      program.methods
+        .buyCoreAsset()
+        .accounts({
+          ...coreAccounts(),
+          buyer: buyer.publicKey,
+          buyerPaymentToken: buyerPayment,
+          seller: seller.publicKey,
+          sellerPayment,
+          feeTreasury: fee,
+          priceHistory: null,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .signers([buyer]);
+
+    const cancelCore = (signer: Keypair) =>
+      program.methods
+        .cancelCoreListing()
+        .accounts({ ...coreAccounts(), seller: signer.publicKey })
+        .signers([signer])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      asset = await mintCoreItem(seller.publicKey);
+    });
+
+    it("rejects listing an asset the signer does not own", async () => {
+      await expectError(
+        program.methods
+          .listCoreAsset(new anchor.BN(100_000_000), paymentMint, null)
+          .accounts({ ...coreAccounts(), seller: buyer.publicKey })
+          .signers([buyer])
+          .rpc(),
+        "NotAssetOwner"
+      );
+    });
+
+    it("lists a Core asset and flags its GameItem", async () => {
+      const signature = await listCore(100_000_000);
+
+      const listing = await program.account.listing.fetch(listingPda(asset));
+      expect(listing.standard).to.have.property("core");
+      expect((await nftProgram.account.gameItem.fetch(gameItemPda(asset))).isListed).to.be.true;
+      const [created] = (await eventsOf(signature)).filter((e) => e.name === "listingCreated");
+      expect(created.data.mint.equals(asset)).to.be.true;
+      expect(created.data.price.toNumber()).to.equal(100_000_000);
+    });
+
+    it("buys the Core asset and moves it to the buyer", async () => {
+      const sellerBefore = await tokenBalance(sellerPayment);
+      const signature = await buyCore().rpc({ commitment: "confirmed" });
+
+      expect((await assetOwner(asset)).toBase58()).to.equal(buyer.publicKey.toBase58());
+      const gameItem = await nftProgram.account.gameItem.fetch(gameItemPda(asset));
+      expect(gameItem.isListed).to.be.false;
+      expect(gameItem.owner.toBase58()).to.equal(buyer.publicKey.toBase58());
+      // 2.5% protocol fee, no royalty plugin on nft-items assets
+      expect((await tokenBalance(sellerPayment)) - sellerBefore).to.equal(97_500_000);
+      expect(await connection.getAccountInfo(listingPda(asset))).to.be.null;
+      const [sale] = (await eventsOf(signature)).filter((e) => e.name === "sale");
+      expect(sale.data.buyer.equals(buyer.publicKey)).to.be.true;
+      expect(sale.data.protocolFee.toNumber()).to.equal(2_500_000);
+      expect(sale.data.royalty.toNumber()).to.equal(0);
+    });
+
+    it("only lets the seller cancel, and then unfreezes the asset for them", async () => {
+      asset = await mintCoreItem(seller.publicKey);
+      await listCore(20_000_000);
+      await expectError(cancelCore(buyer), "ConstraintHasOne");
+
+      const signature = await cancelCore(seller);
+      expect((await assetOwner(asset)).toBase58()).to.equal(seller.publicKey.toBase58());
+      expect((await nftProgram.account.gameItem.fetch(gameItemPda(asset))).isListed).to.be.false;
+      expect(await connection.getAccountInfo(listingPda(asset))).to.be.null;
+      const [cancelled] = (await eventsOf(signature)).filter((e) => e.name === "listingCancelled");
+      expect(cancelled.data.mint.equals(asset)).to.be.true;
+      expect(cancelled.data.expired).to.be.false;
+    });
+
+    describe("with collection royalties", () => {
+      const creator = Keypair.generate();
+      let creatorPayment: PublicKey;
+
+      before(async () => {
+        creatorPayment = await tokenAccount(paymentMint, creator.publicKey);
+        await setCollectionRoyalties(creator.publicKey, 1_000);
+        asset = await mintCoreItem(seller.publicKey);
+        await listCore(100_000_000);
+      });
+
+      after(async () => {
+        await setCollectionRoyalties(null);
+      });
+
+      it("rejects a purchase that leaves out the royalty creator", async () => {
+        await expectError(buyCore().rpc(), "InvalidRoyaltyRecipient");
+      });
+
+      it("rejects a purchase that pays a different fee treasury", async () => {
+        await expectError(
+          buyCore(sellerPayment)
+            .remainingAccounts([writable(creatorPayment)])
+            .rpc(),
+          "InvalidFeeTreasury"
+        );
+      });
+
+      it("pays the creator their royalty before the seller", async () => {
+        const sellerBefore = await tokenBalance(sellerPayment);
+        const signature = await buyCore()
+          .remainingAccounts([writable(creatorPayment)])
+          .rpc({ commitment: "confirmed" });
+
+        // 10% royalty and 2.5% fee out of 100
+        expect(await tokenBalance(creatorPayment)).to.equal(10_000_000);
+        expect((await tokenBalance(sellerPayment)) - sellerBefore).to.equal(87_500_000);
+        const [sale] = (await eventsOf(signature)).filter((e) => e.name === "sale");
+        expect(sale.data.royalty.toNumber()).to.equal(10_000_000);
+      });
+    });
+  });
+
+  describe("SPL escrow listings", () => {
+    let nft: { mint: PublicKey; account: PublicKey };
+    let buyerNft: PublicKey;
+
+    const updateListing = (signer: Keypair, price: number, isActive: boolean) =>
+      program.methods
+        .updateListing(new anchor.BN(price), isActive)
+        .accounts({ listing: listingPda(nft.mint), seller: signer.publicKey })
+        .signers([signer])
+        .rpc({ commitment: "confirmed" });
+
+    const cancelListing = (signer: Keypair, listed: { mint: PublicKey; account: PublicKey }) =>
+      program.methods
+        .cancelListing()
+        .accounts({
+          listing: listingPda(listed.mint),
+          escrowNftToken: escrowPda(listed.mint),
+          seller: signer.publicKey,
+          sellerNftToken: listed.account,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      nft = await mintSplNft(seller.publicKey);
+      buyerNft = await tokenAccount(nft.mint, buyer.publicKey);
+    });
+
+    it("rejects a payment mint the marketplace does not accept", async () => {
+      const otherMint = await createMint(connection, wallet.payer, wallet.publicKey, null, 6);
+      await expectError(listNft(nft, 50_000_000, otherMint).rpc(), "UnsupportedPaymentMint");
+    });
+
+    it("rejects an expiry that has already passed", async () => {
+      const expiresAt = new anchor.BN((await chainTime()) - 1);
+      await expectError(listNft(nft, 50_000_000, paymentMint, expiresAt).rpc(), "InvalidExpiry");
+    });
+
+    it("escrows the NFT and emits ListingCreated", async () => {
+      const signature = await listNft(nft, 50_000_000).rpc({ commitment: "confirmed" });
+
+      expect(await tokenBalance(nft.account)).to.equal(0);
+      expect(await tokenBalance(escrowPda(nft.mint))).to.equal(1);
+      const listing = await program.account.listing.fetch(listingPda(nft.mint));
+      expect(listing.standard).to.have.property("splToken");
+      const [created] = (await eventsOf(signature)).filter((e) => e.name === "listingCreated");
+      expect(created.data.mint.equals(nft.mint)).to.be.true;
+      expect(created.data.price.toNumber()).to.equal(50_000_000);
+      expect(created.data.expiresAt).to.be.null;
+    });
+
+    it("only lets the seller update the listing", async () => {
+      await expectError(updateListing(buyer, 1, true), "ConstraintHasOne");
+    });
+
+    it("rejects purchases while the listing is paused", async () => {
+      const signature = await updateListing(seller, 60_000_000, false);
+      const [updated] = (await eventsOf(signature)).filter((e) => e.name === "listingUpdated");
+      expect(updated.data.price.toNumber()).to.equal(60_000_000);
+      expect(updated.data.isActive).to.be.false;
+
+      await expectError(
+        program.methods.buyNft().accounts(buyNftAccounts(nft.mint, buyerNft)).signers([buyer]).rpc(),
+        "ListingNotActive"
+      );
+      await updateListing(seller, 60_000_000, true);
+    });
+
+    it("rejects a purchase that pays a different fee treasury", async () => {
+      await expectError(
+        program.methods
+          .buyNft()
+          .accounts({ ...buyNftAccounts(nft.mint, buyerNft), feeTreasury: sellerPayment })
+          .signers([buyer])
+          .rpc(),
+        "InvalidFeeTreasury"
+      );
+    });
+
+    it("pays the seller and treasury and records the sale", async () => {
+      const priceHistory = await initializePriceHistory(nft.mint);
+      const sellerBefore = await tokenBalance(sellerPayment);
+      const treasuryBefore = await tokenBalance(treasuryPayment);
+
+      const signature = await program.methods
+        .buyNft()
+        .accounts({ ...buyNftAccounts(nft.mint, buyerNft), priceHistory })
+        .signers([buyer])
+        .rpc({ commitment: "confirmed" });
+
+      expect(await tokenBalance(buyerNft)).to.equal(1);
+      expect((await tokenBalance(sellerPayment)) - sellerBefore).to.equal(58_500_000);
+      expect((await tokenBalance(treasuryPayment)) - treasuryBefore).to.equal(1_500_000);
+      expect(await connection.getAccountInfo(listingPda(nft.mint))).to.be.null;
+      expect(await connection.getAccountInfo(escrowPda(nft.mint))).to.be.null;
+
+      const [sale] = (await eventsOf(signature)).filter((e) => e.name === "sale");
+      expect(sale.data.price.toNumber()).to.equal(60_000_000);
+      expect(sale.data.protocolFee.toNumber()).to.equal(1_500_000);
+      const history = await program.account.priceHistory.fetch(priceHistory);
+      expect(history.len).to.equal(1);
+      expect(history.prices[0].toNumber()).to.equal(60_000_000);
+    });
+
+    it("only lets the seller cancel, and then returns the NFT", async () => {
+      const listed = await mintSplNft(seller.publicKey);
+      await listNft(listed, 10_000_000).rpc();
+      await expectError(cancelListing(buyer, listed), "ConstraintHasOne");
+
+      const signature = await cancelListing(seller, listed);
+      expect(await tokenBalance(listed.account)).to.equal(1);
+      expect(await connection.getAccountInfo(listingPda(listed.mint))).to.be.null;
+      const [cancelled] = (await eventsOf(signature)).filter((e) => e.name === "listingCancelled");
+      expect(cancelled.data.mint.equals(listed.mint)).to.be.true;
+      expect(cancelled.data.expired).to.be.false;
+    });
+  });
+
+  describe("SOL listings", () => {
+    const PRICE = anchor.web3.LAMPORTS_PER_SOL;
+    const FEE = (PRICE * FEE_BPS) / 10_000;
+    let nft: { mint: PublicKey; account: PublicKey };
+    let buyerNft: PublicKey;
+
+    before(async () => {
+      nft = await mintSplNft(seller.publicKey);
+      buyerNft = await tokenAccount(nft.mint, buyer.publicKey);
+      await listNft(nft, PRICE, NATIVE_SOL).rpc();
+    });
+
+    it("rejects proceeds sent to a wallet other than the seller", async () => {
+      await expectError(
+        program.methods
+          .buyNft()
+          .accounts({ ...buyNftAccounts(nft.mint, buyerNft, true), sellerPayment: buyer.publicKey })
+          .signers([buyer])
+          .rpc(),
+        "InvalidPaymentAccount"
+      );
+    });
+
+    it("pays the seller and treasury wallets in lamports", async () => {
+      // The seller also gets back the rent of the listing and its escrow
+      const rent =
+        (await connection.getBalance(listingPda(nft.mint))) + (await connection.getBalance(escrowPda(nft.mint)));
+      const sellerBefore = await connection.getBalance(seller.publicKey);
+      const treasuryBefore = await connection.getBalance(feeTreasury.publicKey);
+
+      await program.methods.buyNft().accounts(buyNftAccounts(nft.mint, buyerNft, true)).signers([buyer]).rpc();
+
+      expect(await tokenBalance(buyerNft)).to.equal(1);
+      expect((await connection.getBalance(feeTreasury.publicKey)) - treasuryBefore).to.equal(FEE);
+      expect((await connection.getBalance(seller.publicKey)) - sellerBefore).to.equal(PRICE - FEE + rent);
+    });
+  });
+
+  describe("Listing expiry", () => {
+    let nft: { mint: PublicKey; account: PublicKey };
+    let buyerNft: PublicKey;
+    let expiresAt: number;
+
+    const closeExpired = () =>
+      program.methods
+        .closeExpiredListing()
+        .accounts({
+          listing: listingPda(nft.mint),
+          escrowNftToken: escrowPda(nft.mint),
+          seller: seller.publicKey,
+          sellerNftToken: nft.account,
+          cranker: buyer.publicKey,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .signers([buyer])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      nft = await mintSplNft(seller.publicKey);
+      buyerNft = await tokenAccount(nft.mint, buyer.publicKey);
+      expiresAt = (await chainTime()) + 5;
+      await listNft(nft, 10_000_000, paymentMint, new anchor.BN(expiresAt)).rpc();
+    });
+
+    it("cannot be cranked before it expires", async () => {
+      await expectError(closeExpired(), "ListingNotExpired");
+    });
+
+    it("rejects purchases once the listing has expired", async () => {
+      await waitForChainTime(expiresAt);
+      await expectError(
+        program.methods.buyNft().accounts(buyNftAccounts(nft.mint, buyerNft)).signers([buyer]).rpc(),
+        "ListingExpired"
+      );
+    });
+
+    it("lets anyone return an expired listing to the seller", async () => {
+      const signature = await closeExpired();
+
+      expect(await tokenBalance(nft.account)).to.equal(1);
+      expect(await connection.getAccountInfo(listingPda(nft.mint))).to.be.null;
+      const [cancelled] = (await eventsOf(signature)).filter((e) => e.name === "listingCancelled");
+      expect(cancelled.data.expired).to.be.true;
+    });
+
+    describe("Core listings", () => {
+      let asset: PublicKey;
+      let coreExpiresAt: number;
+
+      const coreAccounts = () => ({
+        listing: listingPda(asset),
+        asset,
+        collection: coreCollection,
+        gameItem: gameItemPda(asset),
+        seller: seller.publicKey,
+        nftItemsProgram: nftProgram.programId,
+        mplCoreProgram: MPL_CORE_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      });
+
+      const closeExpiredCore = () =>
+        program.methods
+          .closeExpiredCoreListing()
+          .accounts({ ...coreAccounts(), cranker: buyer.publicKey })
+          .signers([buyer])
+          .rpc({ commitment: "confirmed" });
+
+      before(async () => {
+        asset = await mintCoreItem(seller.publicKey);
+        coreExpiresAt = (await chainTime()) + 5;
+        await program.methods
+          .listCoreAsset(new anchor.BN(10_000_000), paymentMint, new anchor.BN(coreExpiresAt))
+          .accounts({ ...coreAccounts(), config: configPda })
+          .signers([seller])
+          .rpc();
+      });
+
+      it("cannot be cranked before it expires", async () => {
+        await expectError(closeExpiredCore(), "ListingNotExpired");
+      });
+
+      it("rejects purchases once the listing has expired", async () => {
+        await waitForChainTime(coreExpiresAt);
+        await expectError(
+          program.methods
+            .buyCoreAsset()
+            .accounts({
+              ...coreAccounts(),
+              config: configPda,
+              buyer: buyer.publicKey,
+              buyerPaymentToken: buyerPayment,
+              sellerPayment,
+              feeTreasury: treasuryPayment,
+              priceHistory: null,
+              tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+            })
+            .signers([buyer])
+            .rpc(),
+          "ListingExpired"
+        );
+      });
+
+      it("lets anyone thaw an expired Core listing back to the seller", async () => {
+        const signature = await closeExpiredCore();
+
+        expect((await assetOwner(asset)).toBase58()).to.equal(seller.publicKey.toBase58());
+        expect((await nftProgram.account.gameItem.fetch(gameItemPda(asset))).isListed).to.be.false;
+        expect(await connection.getAccountInfo(listingPda(asset))).to.be.null;
+        const [cancelled] = (await eventsOf(signature)).filter((e) => e.name === "listingCancelled");
+        expect(cancelled.data.expired).to.be.true;
+
+        // Thawed and undelegated, so the seller can list it again
+        await program.methods
+          .listCoreAsset(new anchor.BN(10_000_000), paymentMint, null)
+          .accounts({ ...coreAccounts(), config: configPda })
+          .signers([seller])
+          .rpc();
+      });
+    });
+  });
+
+  describe("Auctions", () => {
+    let walletPayment: PublicKey;
+
+    const auctionAccounts = (mint: PublicKey) => {
+      const auction = pda(Buffer.from("auction"), mint.toBuffer());
+      return {
+        auction,
+        nftEscrow: pda(Buffer.from("auction_escrow"), auction.toBuffer()),
+        bidEscrow: pda(Buffer.from("bid_escrow"), auction.toBuffer()),
+      };
+    };
+
+    const createAuction = async (
+      kind: object,
+      startPrice: number,
+      reservePrice: number,
+      minIncrement: number,
+      duration: number
+    ) => {
+      const nft = await mintSplNft(seller.publicKey);
+      const signature = await program.methods
+        .createAuction(
+          kind as any,
+          new anchor.BN(startPrice),
+          new anchor.BN(reservePrice),
+          new anchor.BN(minIncrement),
+          new anchor.BN(duration),
+          new anchor.BN(0)
+        )
+        .accounts({
+          ...auctionAccounts(nft.mint),
+          seller: seller.publicKey,
+          sellerNftToken: nft.account,
+          nftMint: nft.mint,
+          config: configPda,
+          paymentMint,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([seller])
+        .rpc({ commitment: "confirmed" });
+      return { ...nft, signature };
+    };
+
+    const placeBid = (mint: PublicKey, bidder: Keypair, bidderPaymentToken: PublicKey, amount: number, previous: PublicKey | null = null) => {
+      const { auction, bidEscrow } = auctionAccounts(mint);
+      return program.methods
+        .placeBid(new anchor.BN(amount))
+        .accounts({
+          auction,
+          bidEscrow,
+          bidder: bidder.publicKey,
+          bidderPaymentToken,
+          previousBidderToken: previous,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .signers([bidder])
+        .rpc({ commitment: "confirmed" });
+    };
+
+    const settleAuction = (nft: { mint: PublicKey; account: PublicKey }, winnerNftToken: PublicKey | null, bidderRefundToken: PublicKey | null) =>
+      program.methods
+        .settleAuction()
+        .accounts({
+          ...auctionAccounts(nft.mint),
+          seller: seller.publicKey,
+          sellerNftToken: nft.account,
+          sellerPaymentToken: sellerPayment,
+          winnerNftToken,
+          bidderRefundToken,
+          config: configPda,
+          feeTreasury: treasuryPayment,
+          priceHistory: null,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      // The wallet is the second bidder
+      walletPayment = await tokenAccount(paymentMint, wallet.publicKey);
+      await mintTo(connection, wallet.payer, paymentMint, walletPayment, wallet.payer, 1_000_000_000);
+    });
+
+    it("rejects auctions without a duration or with a rising Dutch price", async () => {
+      await expectError(createAuction({ english: {} }, 10_000_000, 0, 1_000_000, 0), "InvalidAuctionParams");
+      await expectError(createAuction({ dutch: {} }, 10_000_000, 20_000_000, 0, 60), "InvalidAuctionParams");
+    });
+
+    describe("English", () => {
+      let nft: { mint: PublicKey; account: PublicKey };
+
+      before(async () => {
+        const created = await createAuction({ english: {} }, 10_000_000, 20_000_000, 5_000_000, 8);
+        nft = created;
+        expect(await tokenBalance(auctionAccounts(nft.mint).nftEscrow)).to.equal(1);
+        const [listed] = (await eventsOf(created.signature)).filter((e) => e.name === "listingCreated");
+        expect(listed.data.listing.equals(auctionAccounts(nft.mint).auction)).to.be.true;
+        expect(listed.data.price.toNumber()).to.equal(10_000_000);
+      });
+
+      it("rejects bids from the seller and below the opening price", async () => {
+        await expectError(placeBid(nft.mint, seller, sellerPayment, 10_000_000), "SellerCannotBid");
+        await expectError(placeBid(nft.mint, buyer, buyerPayment, 5_000_000), "BidTooLow");
+      });
+
+      it("refunds the outbid bidder and enforces the minimum increment", async () => {
+        const buyerBefore = await tokenBalance(buyerPayment);
+        await placeBid(nft.mint, buyer, buyerPayment, 10_000_000);
+        await expectError(placeBid(nft.mint, wallet.payer, walletPayment, 12_000_000, buyerPayment), "BidTooLow");
+        await placeBid(nft.mint, wallet.payer, walletPayment, 25_000_000, buyerPayment);
+
+        expect(await tokenBalance(buyerPayment)).to.equal(buyerBefore);
+        expect(await tokenBalance(auctionAccounts(nft.mint).bidEscrow)).to.equal(25_000_000);
+        const auction = await program.account.auction.fetch(auctionAccounts(nft.mint).auction);
+        expect(auction.highestBidder.equals(wallet.publicKey)).to.be.true;
+      });
+
+      it("cannot be cancelled with bids or settled before it ends", async () => {
+        const { auction, nftEscrow, bidEscrow } = auctionAccounts(nft.mint);
+        await expectError(
+          program.methods
+            .cancelAuction()
+            .accounts({
+              auction,
+              nftEscrow,
+              bidEscrow,
+              seller: seller.publicKey,
+              sellerNftToken: nft.account,
+              tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+            })
+            .signers([seller])
+            .rpc(),
+          "AuctionHasBids"
+        );
+        const winnerNft = await tokenAccount(nft.mint, wallet.publicKey);
+        await expectError(settleAuction(nft, winnerNft, null), "AuctionNotEnded");
+      });
+
+      it("sells to the highest bidder once it ends", async () => {
+        const auction = await program.account.auction.fetch(auctionAccounts(nft.mint).auction);
+        await waitForChainTime(auction.endTime.toNumber());
+        await expectError(placeBid(nft.mint, buyer, buyerPayment, 40_000_000, walletPayment), "AuctionEnded");
+
+        const winnerNft = await tokenAccount(nft.mint, wallet.publicKey);
+        const sellerBefore = await tokenBalance(sellerPayment);
+        const signature = await settleAuction(nft, winnerNft, null);
+
+        expect(await tokenBalance(winnerNft)).to.equal(1);
+        expect((await tokenBalance(sellerPayment)) - sellerBefore).to.equal(24_375_000);
+        expect(await connection.getAccountInfo(auctionAccounts(nft.mint).auction)).to.be.null;
+        const [sale] = (await eventsOf(signature)).filter((e) => e.name === "sale");
+        expect(sale.data.buyer.equals(wallet.publicKey)).to.be.true;
+        expect(sale.data.price.toNumber()).to.equal(25_000_000);
+      });
+
+      it("returns the NFT and refunds the bidder when the reserve is missed", async () => {
+        const unsold = await createAuction({ english: {} }, 10_000_000, 50_000_000, 1_000_000, 3);
+        const buyerBefore = await tokenBalance(buyerPayment);
+        await placeBid(unsold.mint, buyer, buyerPayment, 10_000_000);
+        const auction = await program.account.auction.fetch(auctionAccounts(unsold.mint).auction);
+        await waitForChainTime(auction.endTime.toNumber());
+
+        const signature = await settleAuction(unsold, null, buyerPayment);
+        expect(await tokenBalance(buyerPayment)).to.equal(buyerBefore);
+        expect(await tokenBalance(unsold.account)).to.equal(1);
+        const events = await eventsOf(signature);
+        expect(events.filter((e) => e.name === "sale")).to.be.empty;
+        expect(events.find((e) => e.name === "listingCancelled").data.expired).to.be.true;
+      });
+
+      it("returns the NFT when an auction without bids is cancelled", async () => {
+        const cancelled = await createAuction({ english: {} }, 10_000_000, 0, 1_000_000, 60);
+        const { auction, nftEscrow, bidEscrow } = auctionAccounts(cancelled.mint);
+        const signature = await program.methods
+          .cancelAuction()
+          .accounts({
+            auction,
+            nftEscrow,
+            bidEscrow,
+            seller: seller.publicKey,
+            sellerNftToken: cancelled.account,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          })
+          .signers([seller])
+          .rpc({ commitment: "confirmed" });
+        expect(await tokenBalance(cancelled.account)).to.equal(1);
+        expect(await connection.getAccountInfo(auction)).to.be.null;
+        const [event] = (await eventsOf(signature)).filter((e) => e.name === "listingCancelled");
+        expect(event.data.expired).to.be.false;
+      });
+    });
+
+    describe("Dutch", () => {
+      let nft: { mint: PublicKey; account: PublicKey };
+
+      before(async () => {
+        nft = await createAuction({ dutch: {} }, 50_000_000, 10_000_000, 0, 1_000);
+      });
+
+      it("does not take bids", async () => {
+        await expectError(placeBid(nft.mint, buyer, buyerPayment, 50_000_000), "WrongAuctionKind");
+      });
+
+      it("sells at the decayed price to the first buyer", async () => {
+        const buyerNft = await tokenAccount(nft.mint, buyer.publicKey);
+        const sellerBefore = await tokenBalance(sellerPayment);
+        const signature = await program.methods
+          .buyDutchAuction()
+          .accounts({
+            ...auctionAccounts(nft.mint),
+            buyer: buyer.publicKey,
+            buyerPaymentToken: buyerPayment,
+            buyerNftToken: buyerNft,
+            seller: seller.publicKey,
+            sellerPaymentToken: sellerPayment,
+            config: configPda,
+            feeTreasury: treasuryPayment,
+            priceHistory: null,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          })
+          .signers([buyer])
+          .rpc({ commitment: "confirmed" });
+
+        const [sale] = (await eventsOf(signature)).filter((e) => e.name === "sale");
+        const price = sale.data.price.toNumber();
+        expect(price).to.be.at.most(50_000_000).and.above(10_000_000);
+        expect((await tokenBalance(sellerPayment)) - sellerBefore).to.equal(price - Math.floor((price * FEE_BPS) / 10_000));
+        expect(await tokenBalance(buyerNft)).to.equal(1);
+      });
+    });
+  });
+
+  describe("Offers", () => {
+    let nft: { mint: PublicKey; account: PublicKey };
+    let buyerNft: PublicKey;
+
+    const offerAccounts = (mint: PublicKey) => {
+      const offer = pda(Buffer.from("offer"), mint.toBuffer(), buyer.publicKey.toBuffer());
+      return { offer, offerEscrow: pda(Buffer.from("offer_escrow"), offer.toBuffer()) };
+    };
+
+    const makeOffer = (mint: PublicKey, amount: number, payment = paymentMint, buyerPaymentToken = buyerPayment) =>
+      program.methods
+        .makeOffer(new anchor.BN(amount))
+        .accounts({
+          ...offerAccounts(mint),
+          buyer: buyer.publicKey,
+          buyerPaymentToken,
+          nftMint: mint,
+          config: configPda,
+          paymentMint: payment,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+
+    const acceptOffer = (signer: Keypair, signerPayment: PublicKey) =>
+      program.methods
+        .acceptOffer()
+        .accounts({
+          ...offerAccounts(nft.mint),
+          buyer: buyer.publicKey,
+          buyerNftToken: buyerNft,
+          seller: signer.publicKey,
+          sellerNftToken: nft.account,
+          sellerPaymentToken: signerPayment,
+          config: configPda,
+          feeTreasury: treasuryPayment,
+          priceHistory: null,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      nft = await mintSplNft(seller.publicKey);
+      buyerNft = await tokenAccount(nft.mint, buyer.publicKey);
+    });
+
+    it("rejects empty offers and unsupported payment mints", async () => {
+      await expectError(makeOffer(nft.mint, 0), "InvalidOfferAmount");
+      const otherMint = await createMint(connection, wallet.payer, wallet.publicKey, null, 6);
+      const otherPayment = await tokenAccount(otherMint, buyer.publicKey);
+      await expectError(makeOffer(nft.mint, 1_000_000, otherMint, otherPayment), "UnsupportedPaymentMint");
+    });
+
+    it("escrows the offered amount", async () => {
+      const buyerBefore = await tokenBalance(buyerPayment);
+      await makeOffer(nft.mint, 30_000_000);
+      expect(buyerBefore - (await tokenBalance(buyerPayment))).to.equal(30_000_000);
+      expect(await tokenBalance(offerAccounts(nft.mint).offerEscrow)).to.equal(30_000_000);
+    });
+
+    it("can only be accepted by the NFT's holder", async () => {
+      await expectError(acceptOffer(buyer, buyerPayment), "ConstraintTokenOwner");
+    });
+
+    it("swaps the NFT for the escrowed payment on accept", async () => {
+      const sellerBefore = await tokenBalance(sellerPayment);
+      const signature = await acceptOffer(seller, sellerPayment);
+
+      expect(await tokenBalance(buyerNft)).to.equal(1);
+      expect((await tokenBalance(sellerPayment)) - sellerBefore).to.equal(29_250_000);
+      expect(await connection.getAccountInfo(offerAccounts(nft.mint).offer)).to.be.null;
+      const [sale] = (await eventsOf(signature)).filter((e) => e.name === "sale");
+      expect(sale.data.seller.equals(seller.publicKey)).to.be.true;
+      expect(sale.data.buyer.equals(buyer.publicKey)).to.be.true;
+    });
+
+    it("refunds the buyer when the offer is cancelled", async () => {
+      const other = await mintSplNft(seller.publicKey);
+      const buyerBefore = await tokenBalance(buyerPayment);
+      await makeOffer(other.mint, 5_000_000);
+      await program.methods
+        .cancelOffer()
+        .accounts({
+          ...offerAccounts(other.mint),
+          buyer: buyer.publicKey,
+          buyerPaymentToken: buyerPayment,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .signers([buyer])
+        .rpc();
+      expect(await tokenBalance(buyerPayment)).to.equal(buyerBefore);
+      expect(await connection.getAccountInfo(offerAccounts(other.mint).offer)).to.be.null;
+    });
+
+    describe("on Core assets", () => {
+      let asset: PublicKey;
+
+      const acceptCoreOffer = (signer: Keypair, signerPayment: PublicKey) =>
+        program.methods
+          .acceptCoreOffer()
+          .accounts({
+            ...offerAccounts(asset),
+            buyer: buyer.publicKey,
+            seller: signer.publicKey,
+            sellerPaymentToken: signerPayment,
+            asset,
+            collection: coreCollection,
+            gameItem: gameItemPda(asset),
+            listingAuthority: listingPda(asset),
+            config: configPda,
+            feeTreasury: treasuryPayment,
+            priceHistory: null,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+            nftItemsProgram: nftProgram.programId,
+            mplCoreProgram: MPL_CORE_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([signer])
+          .rpc({ commitment: "confirmed" });
+
+      before(async () => {
+        asset = await mintCoreItem(seller.publicKey);
+        await program.methods
+          .makeCoreOffer(new anchor.BN(20_000_000))
+          .accounts({
+            ...offerAccounts(asset),
+            buyer: buyer.publicKey,
+            buyerPaymentToken: buyerPayment,
+            asset,
+            config: configPda,
+            paymentMint,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([buyer])
+          .rpc();
+      });
+
+      it("escrows an offer keyed by the asset", async () => {
+        const offer = await program.account.offer.fetch(offerAccounts(asset).offer);
+        expect(offer.nftMint.equals(asset)).to.be.true;
+        expect(await tokenBalance(offerAccounts(asset).offerEscrow)).to.equal(20_000_000);
+      });
+
+      it("can only be accepted by the asset's owner", async () => {
+        await expectError(acceptCoreOffer(buyer, buyerPayment), "NotAssetOwner");
+      });
+
+      it("transfers the asset for the escrowed payment on accept", async () => {
+        const sellerBefore = await tokenBalance(sellerPayment);
+        const signature = await acceptCoreOffer(seller, sellerPayment);
+
+        expect((await assetOwner(asset)).toBase58()).to.equal(buyer.publicKey.toBase58());
+        expect((await nftProgram.account.gameItem.fetch(gameItemPda(asset))).owner.toBase58()).to.equal(
+          buyer.publicKey.toBase58()
+        );
+        // 2.5% protocol fee, no royalty plugin on the collection
+        expect((await tokenBalance(sellerPayment)) - sellerBefore).to.equal(19_500_000);
+        expect(await connection.getAccountInfo(offerAccounts(asset).offer)).to.be.null;
+        const [sale] = (await eventsOf(signature)).filter((e) => e.name === "sale");
+        expect(sale.data.mint.equals(asset)).to.be.true;
+      });
+    });
+  });
+
+  describe("Collection bids", () => {
+    const bidId = new anchor.BN(1);
+    const bidPda = pda(Buffer.from("collection_bid"), buyer.publicKey.toBuffer(), bidId.toArrayLike(Buffer, "le", 8));
+    const bidEscrow = pda(Buffer.from("collection_bid_escrow"), bidPda.toBuffer());
+
+    const fillAccounts = (asset: PublicKey) => ({
+      bid: bidPda,
+      bidEscrow,
+      buyer: buyer.publicKey,
+      seller: seller.publicKey,
+      sellerPaymentToken: sellerPayment,
+      asset,
+      collection: coreCollection,
+      gameItem: gameItemPda(asset),
+      listingAuthority: listingPda(asset),
+      config: configPda,
+      feeTreasury: treasuryPayment,
+      priceHistory: null,
+      tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+      nftItemsProgram: nftProgram.programId,
+      mplCoreProgram: MPL_CORE_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+
+    before(async () => {
+      await program.methods
+        .makeCollectionBid(bidId, new anchor.BN(10_000_000), 2, { weapon: {} }, 2)
+        .accounts({
+          bid: bidPda,
+          bidEscrow,
+          buyer: buyer.publicKey,
+          buyerPaymentToken: buyerPayment,
+          collection: coreCollection,
+          config: configPda,
+          paymentMint,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+      expect(await tokenBalance(bidEscrow)).to.equal(20_000_000);
+    });
+
+    it("rejects an item below the bid's minimum rarity", async () => {
+      const asset = await mintCoreItem(seller.publicKey, { weapon: {} }, 1);
+      await expectError(
+        program.methods.fillCollectionBid().accounts(fillAccounts(asset)).signers([seller]).rpc(),
+        "ItemDoesNotMatchBid"
+      );
+    });
+
+    it("rejects an item equipped on the seller's fighter", async () => {
+      const asset = await mintCoreItem(seller.publicKey);
+      await fighterProgram.methods
+        .initializefighter("Nami")
+        .accounts({ fighter: fighterPda(seller.publicKey), user: seller.publicKey, systemProgram: SystemProgram.programId })
+        .signers([seller])
+        .rpc();
+      await fighterProgram.methods
+        .equipItem()
+        .accounts({
+          fighter: fighterPda(seller.publicKey),
+          authority: seller.publicKey,
+          asset,
+          gameItem: gameItemPda(asset),
+          nftItemsProgram: nftProgram.programId,
+        })
+        .signers([seller])
+        .rpc();
+
+      await expectError(
+        program.methods.fillCollectionBid().accounts(fillAccounts(asset)).signers([seller]).rpc(),
+        "ItemUnavailable"
+      );
+    });
+
+    it("fills the bid with a matching item", async () => {
+      const asset = await mintCoreItem(seller.publicKey);
+      const sellerBefore = await tokenBalance(sellerPayment);
+      await program.methods.fillCollectionBid().accounts(fillAccounts(asset)).signers([seller]).rpc();
+
+      expect((await assetOwner(asset)).toBase58()).to.equal(buyer.publicKey.toBase58());
+      expect((await tokenBalance(sellerPayment)) - sellerBefore).to.equal(9_750_000);
+      expect((await program.account.collectionBid.fetch(bidPda)).quantity).to.equal(1);
+    });
+
+    it("refunds the rest of the escrow on cancel", async () => {
+      const buyerBefore = await tokenBalance(buyerPayment);
+      await program.methods
+        .cancelCollectionBid()
+        .accounts({
+          bid: bidPda,
+          bidEscrow,
+          buyer: buyer.publicKey,
+          buyerPaymentToken: buyerPayment,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .signers([buyer])
+        .rpc();
+      expect((await tokenBalance(buyerPayment)) - buyerBefore).to.equal(10_000_000);
+      expect(await connection.getAccountInfo(bidPda)).to.be.null;
+    });
+  });
+
+  describe("Bundles", () => {
+    const creator = Keypair.generate();
+    let creatorPayment: PublicKey;
+
+    const bundlePda = (bundleId: anchor.BN) =>
+      pda(Buffer.from("bundle"), seller.publicKey.toBuffer(), bundleId.toArrayLike(Buffer, "le", 8));
+    const coreItemAccounts = (asset: PublicKey) => [
+      writable(asset),
+      writable(coreCollection),
+      writable(gameItemPda(asset)),
+      readonly(listingPda(asset)),
+    ];
+
+    // Lists one SPL NFT and one Core item, priced separately
+    const listBundle = async (bundleId: anchor.BN, splPrice: number, corePrice: number) => {
+      const spl = await mintSplNft(seller.publicKey);
+      const asset = await mintCoreItem(seller.publicKey);
+      const bundle = bundlePda(bundleId);
+      const escrow = getAssociatedTokenAddressSync(spl.mint, bundle, true);
+      const signature = await program.methods
+        .listBundle(bundleId, paymentMint, [
+          { standard: { splToken: {} }, mint: spl.mint, price: new anchor.BN(splPrice) },
+          { standard: { core: {} }, mint: asset, price: new anchor.BN(corePrice) },
+        ])
+        .accounts({
+          bundle,
+          seller: seller.publicKey,
+          config: configPda,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          nftItemsProgram: nftProgram.programId,
+          mplCoreProgram: MPL_CORE_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([readonly(spl.mint), writable(spl.account), writable(escrow), ...coreItemAccounts(asset)])
+        .signers([seller])
+        .rpc({ commitment: "confirmed" });
+      return { bundle, spl, escrow, asset, signature };
+    };
+
+    before(async () => {
+      creatorPayment = await tokenAccount(paymentMint, creator.publicKey);
+      await setCollectionRoyalties(creator.publicKey, 500);
+    });
+
+    after(async () => {
+      await setCollectionRoyalties(null);
+    });
+
+    it("sums the item prices into the bundle price and emits a listing per item", async () => {
+      const { bundle, spl, asset, signature } = await listBundle(new anchor.BN(1), 40_000_000, 60_000_000);
+      const listing = await program.account.bundleListing.fetch(bundle);
+      expect(listing.price.toNumber()).to.equal(100_000_000);
+      expect(listing.items.map((item) => item.price.toNumber())).to.deep.equal([40_000_000, 60_000_000]);
+      expect((await nftProgram.account.gameItem.fetch(gameItemPda(asset))).isListed).to.be.true;
+
+      const created = (await eventsOf(signature)).filter((e) => e.name === "listingCreated");
+      expect(created.map((e) => e.data.mint.toBase58())).to.deep.equal([spl.mint.toBase58(), asset.toBase58()]);
+      expect(created.every((e) => e.data.listing.equals(bundle))).to.be.true;
+    });
+
+    it("pays royalties on the Core item's own price when the bundle is bought", async () => {
+      const bundleId = new anchor.BN(1);
+      const bundle = bundlePda(bundleId);
+      const listing = await program.account.bundleListing.fetch(bundle);
+      const [splItem, coreItem] = listing.items;
+      const buyerNft = await tokenAccount(splItem.mint, buyer.publicKey);
+      const sellerBefore = await tokenBalance(sellerPayment);
+      const treasuryBefore = await tokenBalance(treasuryPayment);
+      const coreHistory = await initializePriceHistory(coreItem.mint);
+
+      const signature = await program.methods
+        .buyBundle()
+        .accounts({
+          bundle,
+          buyer: buyer.publicKey,
+          buyerPaymentToken: buyerPayment,
+          seller: seller.publicKey,
+          sellerPayment,
+          config: configPda,
+          feeTreasury: treasuryPayment,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          nftItemsProgram: nftProgram.programId,
+          mplCoreProgram: MPL_CORE_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          writable(getAssociatedTokenAddressSync(splItem.mint, bundle, true)),
+          writable(buyerNft),
+          readonly(program.programId), // no price history for the SPL item
+          ...coreItemAccounts(coreItem.mint),
+          writable(coreHistory),
+          writable(creatorPayment),
+        ])
+        .signers([buyer])
+        .rpc({ commitment: "confirmed" });
+
+      // 5% royalty on the 60 Core item only, 2.5% fee on both items
+      expect(await tokenBalance(creatorPayment)).to.equal(3_000_000);
+      expect((await tokenBalance(treasuryPayment)) - treasuryBefore).to.equal(2_500_000);
+      expect((await tokenBalance(sellerPayment)) - sellerBefore).to.equal(39_000_000 + 55_500_000);
+      expect(await tokenBalance(buyerNft)).to.equal(1);
+      expect((await assetOwner(coreItem.mint)).toBase58()).to.equal(buyer.publicKey.toBase58());
+      expect(await connection.getAccountInfo(bundle)).to.be.null;
+
+      const sales = (await eventsOf(signature)).filter((e) => e.name === "sale");
+      expect(sales.map((e) => e.data.price.toNumber())).to.deep.equal([40_000_000, 60_000_000]);
+      expect(sales.map((e) => e.data.royalty.toNumber())).to.deep.equal([0, 3_000_000]);
+      const history = await program.account.priceHistory.fetch(coreHistory);
+      expect(history.len).to.equal(1);
+      expect(history.prices[0].toNumber()).to.equal(60_000_000);
+    });
+
+    it("returns every item and emits a cancellation per item", async () => {
+      const { bundle, spl, escrow, asset } = await listBundle(new anchor.BN(2), 10_000_000, 10_000_000);
+      const signature = await program.methods
+        .cancelBundle()
+        .accounts({
+          bundle,
+          seller: seller.publicKey,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          nftItemsProgram: nftProgram.programId,
+          mplCoreProgram: MPL_CORE_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([writable(escrow), writable(spl.account), ...coreItemAccounts(asset)])
+        .signers([seller])
+        .rpc({ commitment: "confirmed" });
+
+      expect(await tokenBalance(spl.account)).to.equal(1);
+      expect((await nftProgram.account.gameItem.fetch(gameItemPda(asset))).isListed).to.be.false;
+      const cancelled = (await eventsOf(signature)).filter((e) => e.name === "listingCancelled");
+      expect(cancelled.map((e) => e.data.mint.toBase58())).to.deep.equal([spl.mint.toBase58(), asset.toBase58()]);
+    });
+  });
+});
@@ -1,8 +1,9 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { NftItems } from "../target/types/nft_items";
+import { FighterRegistry } from "../target/types/fighter_registry";
 import { expect } from "chai";
-import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { Keypair, PublicKey, SystemProgram, Transaction, TransactionInstruction } from "@solana/web3.js";
 
 describe("Pirates Quest NFT Items", () => {
   // Configure the client to use the local cluster
@@ -10,7 +11,12 @@ describe("Pirates Quest NFT Items", () => {
   anchor.setProvider(provider);
 
   const program = anchor.workspace.NftItems as Program<NftItems>;
+  const fighterProgram = anchor.workspace.FighterRegistry as Program<FighterRegistry>;
   const wallet = provider.wallet as anchor.Wallet;
+  const [fighterPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("fighter"), wallet.publicKey.toBuffer()],
+    fighterProgram.programId
+  );
 
   // Test accounts
   let collectionPda: PublicKey;
@@ -84,6 +90,72 @@ describe("Pirates Quest NFT Items", () => {
         expect(error).to.exist;
       }
     });
+
+    it("should hand the collection authority over in two steps", async () => {
+      const newAuthority = Keypair.generate();
+      await program.methods
+        .proposeAuthority(newAuthority.publicKey)
+        .accounts({ collection: collectionPda, authority: wallet.publicKey })
+        .rpc();
+
+      try {
+        await program.methods
+          .acceptAuthority()
+          .accounts({ collection: collectionPda, newAuthority: wallet.publicKey })
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.toString()).to.include("NoPendingAuthority");
+      }
+
+      await program.methods
+        .acceptAuthority()
+        .accounts({ collection: collectionPda, newAuthority: newAuthority.publicKey })
+        .signers([newAuthority])
+        .rpc();
+      let collectionAccount = await program.account.collection.fetch(collectionPda);
+      expect(collectionAccount.authority.toString()).to.equal(newAuthority.publicKey.toString());
+      expect(collectionAccount.pendingAuthority).to.be.null;
+
+      // Hand it back so later tests keep the wallet as authority
+      await program.methods
+        .proposeAuthority(wallet.publicKey)
+        .accounts({ collection: collectionPda, authority: newAuthority.publicKey })
+        .signers([newAuthority])
+        .rpc();
+      await program.methods
+        .acceptAuthority()
+        .accounts({ collection: collectionPda, newAuthority: wallet.publicKey })
+        .rpc();
+      collectionAccount = await program.account.collection.fetch(collectionPda);
+      expect(collectionAccount.authority.toString()).to.equal(wallet.publicKey.toString());
+    });
+
+    it("should only let the authority migrate the collection", async () => {
+      const stranger = Keypair.generate();
+      try {
+        await program.methods
+          .migrateCollection()
+          .accounts({ collection: collectionPda, authority: stranger.publicKey, systemProgram: SystemProgram.programId })
+          .signers([stranger])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.toString()).to.include("UnauthorizedAuthority");
+      }
+    });
+
+    it("should not migrate a collection that already has pending_authority", async () => {
+      try {
+        await program.methods
+          .migrateCollection()
+          .accounts({ collection: collectionPda, authority: wallet.publicKey, systemProgram: SystemProgram.programId })
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.toString()).to.include("AlreadyMigrated");
+      }
+    });
   });
 
   describe("Minting Game Items", () => {
@@ -365,7 +437,105 @@ describe("Pirates Quest NFT Items", () => {
   });
 
   describe("Treasury Drop Minting", () => {
+    const [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("registry_config")],
+      fighterProgram.programId
+    );
+    const progressAccounts = {
+      fighter: fighterPda,
+      config: configPda,
+      gameServer: wallet.publicKey,
+    };
+
+    const treasuryClaimPda = (player: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("treasury_claim"), player.toBuffer()],
+        program.programId
+      )[0];
+
+    const mintTreasury = (asset: Keypair, claimedConquest: boolean, owner: Keypair | null = null) => {
+      const [itemPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("game_item"), asset.publicKey.toBuffer()],
+        program.programId
+      );
+      const ownerKey = owner ? owner.publicKey : wallet.publicKey;
+      return program.methods
+        .mintTreasuryDrop(
+          "Crown of the Pirate King",
+          "https://arweave.net/treasury-metadata.json",
+          { artifact: {} },
+          5,
+          { attackPower: 1000, defense: 500, speedBoost: 200, specialAbility: 99 },
+          {
+            claimTimestamp: new anchor.BN(Date.now() / 1000),
+            player: wallet.publicKey,
+            allIslandsConquered: claimedConquest,
+            finalBattleScore: 9999,
+          }
+        )
+        .accounts({
+          collection: collectionPda,
+          collectionMint: collectionMint.publicKey,
+          gameItem: itemPda,
+          asset: asset.publicKey,
+          payer: wallet.publicKey,
+          owner: ownerKey,
+          treasuryClaim: treasuryClaimPda(ownerKey),
+          fighter: fighterPda,
+          mplCoreProgram: MPL_CORE_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers(owner ? [asset, owner] : [asset]);
+    };
+
+    before(async () => {
+      await fighterProgram.methods
+        .initializefighter("Zoro")
+        .accounts({
+          fighter: fighterPda,
+          user: wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // The wallet acts as game server so it can record progress
+      if (!(await fighterProgram.account.registryConfig.fetchNullable(configPda))) {
+        await fighterProgram.methods
+          .initializeConfig()
+          .accounts({
+            config: configPda,
+            admin: wallet.publicKey,
+            program: fighterProgram.programId,
+            programData: PublicKey.findProgramAddressSync(
+              [fighterProgram.programId.toBuffer()],
+              new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+            )[0],
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+      await fighterProgram.methods
+        .addGameServer(wallet.publicKey)
+        .accounts({ config: configPda, admin: wallet.publicKey })
+        .rpc();
+    });
+
+    it("should reject a treasury drop before every island is conquered", async () => {
+      try {
+        await mintTreasury(Keypair.generate(), true).rpc();
+        expect.fail("Should have failed");
+      } catch (error: any) {
+        expect(error.toString()).to.include("IslandsNotConquered");
+      }
+    });
+
     it("should mint a treasury drop for game completion", async () => {
+      // Level 20 unlocks the last island; each boss opens the next one
+      await fighterProgram.methods.grantExperience(36100).accounts(progressAccounts).rpc();
+      for (const island of [1, 2, 3, 4]) {
+        await fighterProgram.methods.recordBossDefeat(island).accounts(progressAccounts).rpc();
+      }
+
       assetKeypair = Keypair.generate();
       [gameItemPda] = PublicKey.findProgramAddressSync(
         [Buffer.from("game_item"), assetKeypair.publicKey.toBuffer()],
@@ -405,6 +575,8 @@ describe("Pirates Quest NFT Items", () => {
           asset: assetKeypair.publicKey,
           payer: wallet.publicKey,
           owner: wallet.publicKey,
+          treasuryClaim: treasuryClaimPda(wallet.publicKey),
+          fighter: fighterPda,
           mplCoreProgram: MPL_CORE_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
@@ -416,7 +588,165 @@ describe("Pirates Quest NFT Items", () => {
       const gameItem = await program.account.gameItem.fetch(gameItemPda);
       expect(gameItem.treasuryProof).to.not.be.null;
       expect(gameItem.treasuryProof.finalBattleScore).to.equal(9999);
+      expect(gameItem.treasuryProof.allIslandsConquered).to.be.true;
       expect(gameItem.stats.attackPower).to.equal(1000);
+
+      const claim = await program.account.treasuryClaim.fetch(treasuryClaimPda(wallet.publicKey));
+      expect(claim.player.toString()).to.equal(wallet.publicKey.toString());
+      expect(claim.asset.toString()).to.equal(assetKeypair.publicKey.toString());
+    });
+
+    it("should not mint a second treasury drop for the same player", async () => {
+      try {
+        await mintTreasury(Keypair.generate(), true).rpc();
+        expect.fail("Should have failed");
+      } catch (error: any) {
+        // The claim marker already exists, so the system program refuses to create it again
+        expect(error.toString()).to.include("custom program error: 0x0");
+      }
+    });
+
+    it("should not mint another player's treasury drop to a different owner", async () => {
+      // The proof names the wallet's player, so a stranger cannot take the drop
+      const stranger = Keypair.generate();
+      try {
+        await mintTreasury(Keypair.generate(), true, stranger).rpc();
+        expect.fail("Should have failed");
+      } catch (error: any) {
+        expect(error.toString()).to.include("TreasuryPlayerMismatch");
+      }
+    });
+  });
+
+  describe("Equipping Items", () => {
+    const equipAccounts = () => ({
+      fighter: fighterPda,
+      authority: wallet.publicKey,
+      asset: assetKeypair.publicKey,
+      gameItem: gameItemPda,
+      nftItemsProgram: program.programId,
+    });
+
+    const unequipAccounts = (caller: PublicKey) => ({
+      fighter: fighterPda,
+      caller,
+      asset: assetKeypair.publicKey,
+      gameItem: gameItemPda,
+      nftItemsProgram: program.programId,
+    });
+
+    // Core TransferV1 straight from the wallet, bypassing the marketplace (optional accounts as the Core program id)
+    const transferAsset = async (newOwner: PublicKey) => {
+      const ix = new TransactionInstruction({
+        programId: MPL_CORE_PROGRAM_ID,
+        keys: [
+          { pubkey: assetKeypair.publicKey, isSigner: false, isWritable: true },
+          { pubkey: collectionMint.publicKey, isSigner: false, isWritable: false },
+          { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
+          { pubkey: wallet.publicKey, isSigner: true, isWritable: false },
+          { pubkey: newOwner, isSigner: false, isWritable: false },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+          { pubkey: MPL_CORE_PROGRAM_ID, isSigner: false, isWritable: false },
+        ],
+        data: Buffer.from([14, 0]), // TransferV1, no compression proof
+      });
+      await provider.sendAndConfirm(new Transaction().add(ix));
+    };
+
+    before(async () => {
+      assetKeypair = Keypair.generate();
+      [gameItemPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("game_item"), assetKeypair.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .mintGameItem("Wado Ichimonji", "https://example.com", { weapon: {} }, 4, {
+          attackPower: 120,
+          defense: 30,
+          speedBoost: 10,
+          specialAbility: 0,
+        })
+        .accounts({
+          collection: collectionPda,
+          collectionMint: collectionMint.publicKey,
+          gameItem: gameItemPda,
+          asset: assetKeypair.publicKey,
+          payer: wallet.publicKey,
+          owner: wallet.publicKey,
+          mplCoreProgram: MPL_CORE_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([assetKeypair])
+        .rpc();
+    });
+
+    it("should equip a weapon and add its stats to the fighter", async () => {
+      await fighterProgram.methods.equipItem().accounts(equipAccounts()).rpc();
+
+      const gameItem = await program.account.gameItem.fetch(gameItemPda);
+      expect(gameItem.isEquipped).to.be.true;
+
+      const fighter = await fighterProgram.account.fighter.fetch(fighterPda);
+      expect(fighter.equipment.weapon.asset.toString()).to.equal(assetKeypair.publicKey.toString());
+
+      const stats = await fighterProgram.methods
+        .getEffectiveStats()
+        .accounts({ fighter: fighterPda })
+        .view();
+      expect(stats.attackPower).to.equal(120);
+      expect(stats.defense).to.equal(30);
+      // Level 20 after conquering every island
+      expect(stats.maxHealth).to.equal(195);
+    });
+
+    it("should not equip the same item twice", async () => {
+      try {
+        await fighterProgram.methods.equipItem().accounts(equipAccounts()).rpc();
+        expect.fail("Should have failed");
+      } catch (error: any) {
+        expect(error.toString()).to.include("AlreadyEquipped");
+      }
+    });
+
+    it("should not delete a fighter with equipped items", async () => {
+      try {
+        await fighterProgram.methods
+          .deletefighter()
+          .accounts({ fighter: fighterPda, authority: wallet.publicKey, user: wallet.publicKey })
+          .rpc();
+        expect.fail("Should have failed");
+      } catch (error: any) {
+        expect(error.toString()).to.include("EquipmentNotEmpty");
+      }
+    });
+
+    it("should unequip the weapon and clear the slot", async () => {
+      await fighterProgram.methods.unequipItem().accounts(unequipAccounts(wallet.publicKey)).rpc();
+
+      const gameItem = await program.account.gameItem.fetch(gameItemPda);
+      expect(gameItem.isEquipped).to.be.false;
+
+      const fighter = await fighterProgram.account.fighter.fetch(fighterPda);
+      expect(fighter.equipment.weapon).to.be.null;
+    });
+
+    it("should let anyone unequip an item once the fighter's authority no longer owns it", async () => {
+      const stranger = Keypair.generate();
+      await fighterProgram.methods.equipItem().accounts(equipAccounts()).rpc();
+
+      try {
+        await fighterProgram.methods.unequipItem().accounts(unequipAccounts(stranger.publicKey)).signers([stranger]).rpc();
+        expect.fail("Should have failed");
+      } catch (error: any) {
+        expect(error.toString()).to.include("UnequipNotAllowed");
+      }
+
+      await transferAsset(stranger.publicKey);
+      await fighterProgram.methods.unequipItem().accounts(unequipAccounts(stranger.publicKey)).signers([stranger]).rpc();
+
+      expect((await program.account.gameItem.fetch(gameItemPda)).isEquipped).to.be.false;
+      const fighter = await fighterProgram.account.fighter.fetch(fighterPda);
+      expect(fighter.equipment.weapon).to.be.null;
     });
   });
 });
\ No newline at end of file
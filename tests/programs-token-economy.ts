@@ -2,12 +2,14 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { TokenEconomy } from "../target/types/token_economy";
 import { expect } from "chai";
-import { PublicKey, Keypair } from "@solana/web3.js";
+import { keccak_256 } from "@noble/hashes/sha3";
+import { PublicKey, Keypair, ComputeBudgetProgram, Ed25519Program } from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
   createMint,
+  getAccount,
+  getMint,
   getOrCreateAssociatedTokenAccount,
-  mintTo,
 } from "@solana/spl-token";
 
 describe("🏴‍☠️ Pirates Quest - Token Economy Tests", () => {
@@ -19,9 +21,98 @@ describe("🏴‍☠️ Pirates Quest - Token Economy Tests", () => {
 
   let mintKeypair: Keypair;
   let piratePda: PublicKey;
-  let playerVault: Keypair;
-  let player2Vault: Keypair;
+  let rolesPda: PublicKey;
+  let loginBonusPda: PublicKey;
+  let rewardSchedulePda: PublicKey;
+  let playerVault: PublicKey;
+  let player2Vault: PublicKey;
   let player2: Keypair;
+  const gameServer = Keypair.generate();
+
+  const vaultPda = (owner: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), mintKeypair.publicKey.toBuffer(), owner.toBuffer()],
+      program.programId
+    )[0];
+
+  const vaultTokens = (vault: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_tokens"), vault.toBuffer()],
+      program.programId
+    )[0];
+
+  const levelReceipt = (player: PublicKey, level: number) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("level_receipt"), piratePda.toBuffer(), player.toBuffer(), Buffer.from([level])],
+      program.programId
+    )[0];
+
+  const treasureReceipt = (player: PublicKey, treasureId: anchor.BN) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("treasure_receipt"), piratePda.toBuffer(), player.toBuffer(), treasureId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  const emissionBudget = (category: number) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("emission"), piratePda.toBuffer(), Buffer.from([category])],
+      program.programId
+    )[0];
+
+  const loginTracker = () =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("login_tracker"), piratePda.toBuffer(), authority.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+  // Game server attestation for a reward, checked by the program through the ed25519 instruction before it
+  let nextNonce = 1;
+  const attest = (category: number, player: PublicKey, rewardId: anchor.BN, variant = 0, signer = gameServer) => {
+    const nonce = new anchor.BN(nextNonce++);
+    const expiresAt = new anchor.BN(Math.floor(Date.now() / 1000) + 300);
+    const message = Buffer.concat([
+      piratePda.toBuffer(),
+      player.toBuffer(),
+      Buffer.from([category]),
+      rewardId.toArrayLike(Buffer, "le", 8),
+      Buffer.from([variant]),
+      nonce.toArrayLike(Buffer, "le", 8),
+      expiresAt.toArrayLike(Buffer, "le", 8),
+    ]);
+    const ix = Ed25519Program.createInstructionWithPrivateKey({ privateKey: signer.secretKey, message });
+    return { nonce, expiresAt, ix };
+  };
+
+  const attestationReceipt = (nonce: anchor.BN) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("attestation"), piratePda.toBuffer(), nonce.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  const claimDailyLogin = () => {
+    const att = attest(2, authority.publicKey, new anchor.BN(0));
+    return program.methods
+      .rewardDailyLogin(att.nonce, att.expiresAt)
+      .accounts({
+        pirate: piratePda,
+        roles: rolesPda,
+        loginBonus: loginBonusPda,
+        emissionBudget: emissionBudget(2),
+        loginTracker: loginTracker(),
+        attestationReceipt: attestationReceipt(att.nonce),
+        mint: mintKeypair.publicKey,
+        fromVault: playerVault,
+        fromVaultTokens: vaultTokens(playerVault),
+        authority: authority.publicKey,
+      })
+      .preInstructions([att.ix])
+      .rpc();
+  };
+
+  const vaultBalance = async (vault: PublicKey) =>
+    new anchor.BN(
+      (await getAccount(provider.connection, vaultTokens(vault))).amount.toString()
+    );
 
   before(async () => {
     console.log("🔧 Setting up test environment...");
@@ -50,27 +141,112 @@ describe("🏴‍☠️ Pirates Quest - Token Economy Tests", () => {
       program.programId
     );
 
+    [rolesPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("roles"), piratePda.toBuffer()],
+      program.programId
+    );
+
     await program.methods
       .initializePirate()
       .accounts({
         pirate: piratePda,
+        roles: rolesPda,
         mint: mintKeypair.publicKey,
         authority: authority.publicKey,
       })
       .rpc();
-    console.log("✅ Pirate PDA initialized");
+    const mintInfo = await getMint(provider.connection, mintKeypair.publicKey);
+    expect(mintInfo.mintAuthority.equals(piratePda)).to.be.true;
+    console.log("✅ Pirate PDA initialized as mint authority");
+
+    await program.methods
+      .addRole({ rewardSigner: {} }, authority.publicKey)
+      .accounts({
+        pirate: piratePda,
+        adminSet: null,
+        proposal: null,
+        roles: rolesPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+    console.log("✅ Admin registered as reward signer");
+
+    await program.methods
+      .addRole({ gameServer: {} }, gameServer.publicKey)
+      .accounts({
+        pirate: piratePda,
+        adminSet: null,
+        proposal: null,
+        roles: rolesPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+    console.log("✅ Local game server key registered");
+
+    [rewardSchedulePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("reward_schedule"), piratePda.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initializeRewardSchedule({
+        levelBase: new anchor.BN(0),
+        levelStep: new anchor.BN(10),
+        levelTable: [],
+        maxLevel: 50,
+        treasureRewards: [50, 100, 250].map((amount) => new anchor.BN(amount)),
+        disabledTreasures: 0b100,
+      })
+      .accounts({
+        pirate: piratePda,
+        adminSet: null,
+        proposal: null,
+        rewardSchedule: rewardSchedulePda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    [loginBonusPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("login_bonus"), piratePda.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initializeLoginBonus([55, 65, 75, 90].map((amount) => new anchor.BN(amount)), 7)
+      .accounts({
+        pirate: piratePda,
+        adminSet: null,
+        proposal: null,
+        loginBonus: loginBonusPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
 
-    playerVault = Keypair.generate();
-    player2Vault = Keypair.generate();
+    const categories = [{ levelCompletion: {} }, { treasure: {} }, { dailyLogin: {} }];
+    for (const [index, category] of categories.entries()) {
+      await program.methods
+        .setEmissionBudget(category as any, new anchor.BN(86400), new anchor.BN(10_000))
+        .accounts({
+          pirate: piratePda,
+          emissionBudget: emissionBudget(index),
+          adminSet: null,
+          proposal: null,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    }
+    console.log("✅ Daily emission budgets configured");
+
+    playerVault = vaultPda(authority.publicKey);
+    player2Vault = vaultPda(player2.publicKey);
 
-    // Always sign for new keypairs
     await program.methods
       .initializeVault()
       .accounts({
-        vault: playerVault.publicKey,
+        vault: playerVault,
+        pirate: piratePda,
+        mint: mintKeypair.publicKey,
+        vaultTokens: vaultTokens(playerVault),
         owner: authority.publicKey,
       })
-      .signers([playerVault])
       .rpc();
 
     const player2Rpc = new anchor.AnchorProvider(
@@ -87,10 +263,13 @@ describe("🏴‍☠️ Pirates Quest - Token Economy Tests", () => {
     await program2.methods
       .initializeVault()
       .accounts({
-        vault: player2Vault.publicKey,
+        vault: player2Vault,
+        pirate: piratePda,
+        mint: mintKeypair.publicKey,
+        vaultTokens: vaultTokens(player2Vault),
         owner: player2.publicKey,
       })
-      .signers([player2Vault, player2])
+      .signers([player2])
       .rpc();
     
     anchor.setProvider(originalProvider);
@@ -101,66 +280,275 @@ describe("🏴‍☠️ Pirates Quest - Token Economy Tests", () => {
   it("Should reward player for completing level 1", async () => {
     console.log("🎮 Testing level completion reward...");
     const level = 1;
+    const att = attest(0, authority.publicKey, new anchor.BN(level));
     await program.methods
-      .rewardLevelCompletion(level)
+      .rewardLevelCompletion(level, att.nonce, att.expiresAt)
       .accounts({
         pirate: piratePda,
-        fromVault: playerVault.publicKey,
+        roles: rolesPda,
+        rewardSchedule: rewardSchedulePda,
+        emissionBudget: emissionBudget(0),
+        receipt: levelReceipt(authority.publicKey, level),
+        attestationReceipt: attestationReceipt(att.nonce),
+        mint: mintKeypair.publicKey,
+        fromVault: playerVault,
+        fromVaultTokens: vaultTokens(playerVault),
         authority: authority.publicKey,
       })
+      .preInstructions([att.ix])
       .rpc();
-    const vaultData = await program.account.vault.fetch(playerVault.publicKey);
-    console.log(`💰 Player earned ${vaultData.balance} PIRATE tokens`);
-    expect(vaultData.balance.toNumber()).to.be.greaterThan(0);
+    const balance = await vaultBalance(playerVault);
+    console.log(`💰 Player earned ${balance} PIRATE tokens`);
+    expect(balance.toNumber()).to.be.greaterThan(0);
   });
 
   it("Should reward player for completing harder level (Level 10)", async () => {
     console.log("🎮 Testing harder level reward...");
     const level = 10;
+    const att = attest(0, authority.publicKey, new anchor.BN(level));
     await program.methods
-      .rewardLevelCompletion(level)
+      .rewardLevelCompletion(level, att.nonce, att.expiresAt)
       .accounts({
         pirate: piratePda,
-        fromVault: playerVault.publicKey,
+        roles: rolesPda,
+        rewardSchedule: rewardSchedulePda,
+        emissionBudget: emissionBudget(0),
+        receipt: levelReceipt(authority.publicKey, level),
+        attestationReceipt: attestationReceipt(att.nonce),
+        mint: mintKeypair.publicKey,
+        fromVault: playerVault,
+        fromVaultTokens: vaultTokens(playerVault),
         authority: authority.publicKey,
       })
+      .preInstructions([att.ix])
       .rpc();
-    const vaultData = await program.account.vault.fetch(playerVault.publicKey);
-    console.log(`💰 Total balance: ${vaultData.balance} PIRATE tokens`);
-    expect(vaultData.balance.toNumber()).to.be.greaterThan(10);
+    const balance = await vaultBalance(playerVault);
+    console.log(`💰 Total balance: ${balance} PIRATE tokens`);
+    expect(balance.toNumber()).to.be.greaterThan(10);
   });
 
   it("Should reward player for finding common treasure", async () => {
     console.log("💎 Testing treasure discovery reward...");
     const treasureType = 1;
+    const treasureId = new anchor.BN(42);
+    const att = attest(1, authority.publicKey, treasureId, treasureType);
     await program.methods
-      .rewardTreasureFound(treasureType)
+      .rewardTreasureFound(treasureType, treasureId, att.nonce, att.expiresAt)
       .accounts({
         pirate: piratePda,
-        fromVault: playerVault.publicKey,
+        roles: rolesPda,
+        rewardSchedule: rewardSchedulePda,
+        emissionBudget: emissionBudget(1),
+        receipt: treasureReceipt(authority.publicKey, treasureId),
+        attestationReceipt: attestationReceipt(att.nonce),
+        mint: mintKeypair.publicKey,
+        fromVault: playerVault,
+        fromVaultTokens: vaultTokens(playerVault),
         authority: authority.publicKey,
       })
+      .preInstructions([att.ix])
       .rpc();
-    const vaultData = await program.account.vault.fetch(playerVault.publicKey);
-    console.log(`🏴‍☠️ Treasure found! Balance: ${vaultData.balance} PIRATE`);
-    expect(vaultData.balance.toNumber()).to.be.greaterThan(0);
+    const balance = await vaultBalance(playerVault);
+    console.log(`🏴‍☠️ Treasure found! Balance: ${balance} PIRATE`);
+    expect(balance.toNumber()).to.be.greaterThan(0);
+  });
+
+  it("Should reject a treasure reward charged to another category's budget", async () => {
+    const treasureId = new anchor.BN(44);
+    const att = attest(1, authority.publicKey, treasureId, 1);
+    try {
+      await program.methods
+        .rewardTreasureFound(1, treasureId, att.nonce, att.expiresAt)
+        .accounts({
+          pirate: piratePda,
+          roles: rolesPda,
+          rewardSchedule: rewardSchedulePda,
+          emissionBudget: emissionBudget(2),
+          receipt: treasureReceipt(authority.publicKey, treasureId),
+          attestationReceipt: attestationReceipt(att.nonce),
+          mint: mintKeypair.publicKey,
+          fromVault: playerVault,
+          fromVaultTokens: vaultTokens(playerVault),
+          authority: authority.publicKey,
+        })
+        .preInstructions([att.ix])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("ConstraintSeeds");
+    }
+  });
+
+  it("Should not reward the same level twice", async () => {
+    try {
+      const att = attest(0, authority.publicKey, new anchor.BN(1));
+      await program.methods
+        .rewardLevelCompletion(1, att.nonce, att.expiresAt)
+        .accounts({
+          pirate: piratePda,
+          roles: rolesPda,
+          rewardSchedule: rewardSchedulePda,
+          emissionBudget: emissionBudget(0),
+          receipt: levelReceipt(authority.publicKey, 1),
+          attestationReceipt: attestationReceipt(att.nonce),
+          mint: mintKeypair.publicKey,
+          fromVault: playerVault,
+          fromVaultTokens: vaultTokens(playerVault),
+          authority: authority.publicKey,
+        })
+        .preInstructions([att.ix])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("RewardAlreadyClaimed");
+    }
+  });
+
+  it("Should reject levels outside the reward schedule", async () => {
+    try {
+      const att = attest(0, authority.publicKey, new anchor.BN(51));
+      await program.methods
+        .rewardLevelCompletion(51, att.nonce, att.expiresAt)
+        .accounts({
+          pirate: piratePda,
+          roles: rolesPda,
+          rewardSchedule: rewardSchedulePda,
+          emissionBudget: emissionBudget(0),
+          receipt: levelReceipt(authority.publicKey, 51),
+          attestationReceipt: attestationReceipt(att.nonce),
+          mint: mintKeypair.publicKey,
+          fromVault: playerVault,
+          fromVaultTokens: vaultTokens(playerVault),
+          authority: authority.publicKey,
+        })
+        .preInstructions([att.ix])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidLevel");
+    }
+  });
+
+  it("Should reject a level table that does not cover max_level", async () => {
+    try {
+      await program.methods
+        .updateRewardSchedule({
+          levelBase: new anchor.BN(0),
+          levelStep: new anchor.BN(10),
+          levelTable: [10, 20, 30].map((amount) => new anchor.BN(amount)),
+          maxLevel: 50,
+          treasureRewards: [50, 100, 250].map((amount) => new anchor.BN(amount)),
+          disabledTreasures: 0b100,
+        })
+        .accounts({
+          pirate: piratePda,
+          adminSet: null,
+          proposal: null,
+          rewardSchedule: rewardSchedulePda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidRewardSchedule");
+    }
+  });
+
+  it("Should reject disabled treasure types", async () => {
+    try {
+      const att = attest(1, authority.publicKey, new anchor.BN(7), 2);
+      await program.methods
+        .rewardTreasureFound(2, new anchor.BN(7), att.nonce, att.expiresAt)
+        .accounts({
+          pirate: piratePda,
+          roles: rolesPda,
+          rewardSchedule: rewardSchedulePda,
+          emissionBudget: emissionBudget(1),
+          receipt: treasureReceipt(authority.publicKey, new anchor.BN(7)),
+          attestationReceipt: attestationReceipt(att.nonce),
+          mint: mintKeypair.publicKey,
+          fromVault: playerVault,
+          fromVaultTokens: vaultTokens(playerVault),
+          authority: authority.publicKey,
+        })
+        .preInstructions([att.ix])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidTreasure");
+    }
+  });
+
+  describe("Game server attestations", () => {
+    const rewardLevel = (level: number, att: ReturnType<typeof attest>, preInstructions = [att.ix]) =>
+      program.methods
+        .rewardLevelCompletion(level, att.nonce, att.expiresAt)
+        .accounts({
+          pirate: piratePda,
+          roles: rolesPda,
+          rewardSchedule: rewardSchedulePda,
+          emissionBudget: emissionBudget(0),
+          receipt: levelReceipt(authority.publicKey, level),
+          attestationReceipt: attestationReceipt(att.nonce),
+          mint: mintKeypair.publicKey,
+          fromVault: playerVault,
+          fromVaultTokens: vaultTokens(playerVault),
+          authority: authority.publicKey,
+        })
+        .preInstructions(preInstructions)
+        .rpc();
+
+    const expectError = async (promise: Promise<unknown>, error: string) => {
+      try {
+        await promise;
+        expect.fail("Should have thrown error");
+      } catch (err) {
+        expect(err.toString()).to.include(error);
+      }
+    };
+
+    it("Should reject rewards without an attestation", async () => {
+      await expectError(rewardLevel(3, attest(0, authority.publicKey, new anchor.BN(3)), []), "MissingAttestation");
+    });
+
+    it("Should reject attestations from an unregistered server", async () => {
+      const att = attest(0, authority.publicKey, new anchor.BN(3), 0, Keypair.generate());
+      await expectError(rewardLevel(3, att), "UnknownGameServer");
+    });
+
+    it("Should reject attestations for a different reward", async () => {
+      await expectError(rewardLevel(4, attest(0, authority.publicKey, new anchor.BN(3))), "AttestationMismatch");
+    });
+
+    it("Should reject a replayed attestation nonce", async () => {
+      const att = attest(0, authority.publicKey, new anchor.BN(3));
+      await rewardLevel(3, att);
+      // Extra compute budget instruction so the replay is not deduplicated as the same transaction
+      const replay = [ComputeBudgetProgram.setComputeUnitLimit({ units: 300_000 }), att.ix];
+      await expectError(rewardLevel(3, att, replay), "AttestationReplayed");
+    });
   });
 
   it("Should give daily login bonus", async () => {
     console.log("📅 Testing daily login reward...");
-    const balanceBefore = (await program.account.vault.fetch(playerVault.publicKey)).balance;
-    await program.methods
-      .rewardDailyLogin()
-      .accounts({
-        pirate: piratePda,
-        fromVault: playerVault.publicKey,
-        authority: authority.publicKey,
-      })
-      .rpc();
-    const balanceAfter = (await program.account.vault.fetch(playerVault.publicKey)).balance;
+    const balanceBefore = (await vaultBalance(playerVault));
+    await claimDailyLogin();
+    const balanceAfter = (await vaultBalance(playerVault));
     const dailyBonus = balanceAfter.sub(balanceBefore);
     console.log(`🎁 Daily bonus received: ${dailyBonus} PIRATE tokens`);
     expect(dailyBonus.toNumber()).to.equal(55);
+
+    const tracker = await program.account.loginTracker.fetch(loginTracker());
+    expect(tracker.streak).to.equal(1);
+  });
+
+  it("Should reject a second daily login claim within 24 hours", async () => {
+    try {
+      await claimDailyLogin();
+      expect.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("LoginBonusNotReady");
+    }
   });
 
   it("Should transfer tokens between players", async () => {
@@ -169,13 +557,17 @@ describe("🏴‍☠️ Pirates Quest - Token Economy Tests", () => {
     await program.methods
       .transferPirateTokens(transferAmount)
       .accounts({
-        fromVault: playerVault.publicKey,
-        toVault: player2Vault.publicKey,
+        pirate: piratePda,
+        fromVault: playerVault,
+        fromVaultTokens: vaultTokens(playerVault),
+        toVault: player2Vault,
+        toVaultTokens: vaultTokens(player2Vault),
+        allowance: null,
         authority: authority.publicKey,
       })
       .rpc();
-    const player1BalanceAfter = (await program.account.vault.fetch(playerVault.publicKey)).balance;
-    const player2Balance = (await program.account.vault.fetch(player2Vault.publicKey)).balance;
+    const player1BalanceAfter = (await vaultBalance(playerVault));
+    const player2Balance = (await vaultBalance(player2Vault));
     console.log(`👤 Player 1 balance: ${player1BalanceAfter} PIRATE`);
     console.log(`👤 Player 2 balance: ${player2Balance} PIRATE`);
     expect(player2Balance.eq(transferAmount)).to.be.true;
@@ -184,16 +576,19 @@ describe("🏴‍☠️ Pirates Quest - Token Economy Tests", () => {
   it("Should burn tokens from player's account", async () => {
     console.log("🔥 Testing token burning...");
     const burnAmount = new anchor.BN(5);
-    const balanceBefore = (await program.account.vault.fetch(playerVault.publicKey)).balance;
+    const balanceBefore = (await vaultBalance(playerVault));
     await program.methods
       .burnPirateTokens(burnAmount)
       .accounts({
         pirate: piratePda,
-        fromVault: playerVault.publicKey,
+        mint: mintKeypair.publicKey,
+        fromVault: playerVault,
+        fromVaultTokens: vaultTokens(playerVault),
+        allowance: null,
         authority: authority.publicKey,
       })
       .rpc();
-    const balanceAfter = (await program.account.vault.fetch(playerVault.publicKey)).balance;
+    const balanceAfter = (await vaultBalance(playerVault));
     const burned = balanceBefore.sub(balanceAfter);
     console.log(`🔥 Burned ${burned} PIRATE tokens`);
     expect(burned.eq(burnAmount)).to.be.true;
@@ -201,14 +596,17 @@ describe("🏴‍☠️ Pirates Quest - Token Economy Tests", () => {
 
   it("Should fail when trying to burn more than balance", async () => {
     console.log("❌ Testing insufficient balance error...");
-    const balance = (await program.account.vault.fetch(playerVault.publicKey)).balance;
+    const balance = (await vaultBalance(playerVault));
     const hugeAmount = balance.add(new anchor.BN(100));
     try {
       await program.methods
         .burnPirateTokens(hugeAmount)
         .accounts({
           pirate: piratePda,
-          fromVault: playerVault.publicKey,
+          mint: mintKeypair.publicKey,
+          fromVault: playerVault,
+          fromVaultTokens: vaultTokens(playerVault),
+          allowance: null,
           authority: authority.publicKey,
         })
         .rpc();
@@ -219,6 +617,887 @@ describe("🏴‍☠️ Pirates Quest - Token Economy Tests", () => {
     }
   });
 
+  it("Should reject rewards signed by an account without the reward signer role", async () => {
+    try {
+      const att = attest(0, player2.publicKey, new anchor.BN(1));
+      await program.methods
+        .rewardLevelCompletion(1, att.nonce, att.expiresAt)
+        .accounts({
+          pirate: piratePda,
+          roles: rolesPda,
+          rewardSchedule: rewardSchedulePda,
+          emissionBudget: emissionBudget(0),
+          receipt: levelReceipt(player2.publicKey, 1),
+          attestationReceipt: attestationReceipt(att.nonce),
+          mint: mintKeypair.publicKey,
+          fromVault: player2Vault,
+          fromVaultTokens: vaultTokens(player2Vault),
+          authority: player2.publicKey,
+        })
+        .signers([player2])
+        .preInstructions([att.ix])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("Unauthorized");
+    }
+  });
+
+  it("Should reject minting by an account without the minter role", async () => {
+    try {
+      await program.methods
+        .mintPirateTokens(new anchor.BN(1_000_000))
+        .accounts({
+          pirate: piratePda,
+          roles: rolesPda,
+          mint: mintKeypair.publicKey,
+          toVault: player2Vault,
+          toVaultTokens: vaultTokens(player2Vault),
+          adminSet: null,
+          proposal: null,
+          authority: player2.publicKey,
+        })
+        .signers([player2])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("Unauthorized");
+    }
+  });
+
+  it("Should let a granted minter mint", async () => {
+    await program.methods
+      .addRole({ minter: {} }, player2.publicKey)
+      .accounts({
+        pirate: piratePda,
+        adminSet: null,
+        proposal: null,
+        roles: rolesPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    const balanceBefore = await vaultBalance(player2Vault);
+    await program.methods
+      .mintPirateTokens(new anchor.BN(25))
+      .accounts({
+        pirate: piratePda,
+        roles: rolesPda,
+        mint: mintKeypair.publicKey,
+        toVault: player2Vault,
+        toVaultTokens: vaultTokens(player2Vault),
+        adminSet: null,
+        proposal: null,
+        authority: player2.publicKey,
+      })
+      .signers([player2])
+      .rpc();
+    const balanceAfter = await vaultBalance(player2Vault);
+    expect(balanceAfter.sub(balanceBefore).toNumber()).to.equal(25);
+
+    await program.methods
+      .revokeRole({ minter: {} }, player2.publicKey)
+      .accounts({
+        pirate: piratePda,
+        adminSet: null,
+        proposal: null,
+        roles: rolesPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+  });
+
+  it("Should reject burning from another player's vault", async () => {
+    try {
+      await program.methods
+        .burnPirateTokens(new anchor.BN(1))
+        .accounts({
+          pirate: piratePda,
+          mint: mintKeypair.publicKey,
+          fromVault: playerVault,
+          fromVaultTokens: vaultTokens(playerVault),
+          allowance: null,
+          authority: player2.publicKey,
+        })
+        .signers([player2])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("Unauthorized");
+    }
+  });
+
+  it("Should reject role changes from a non-admin", async () => {
+    try {
+      await program.methods
+        .addRole({ minter: {} }, player2.publicKey)
+        .accounts({
+          pirate: piratePda,
+          adminSet: null,
+          proposal: null,
+          roles: rolesPda,
+          authority: player2.publicKey,
+        })
+        .signers([player2])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("Unauthorized");
+    }
+  });
+
+  it("Should stop treasure rewards once the daily budget is exhausted", async () => {
+    const budget = await program.account.emissionBudget.fetch(emissionBudget(1));
+    await program.methods
+      .setEmissionBudget({ treasure: {} }, new anchor.BN(86400), budget.emitted)
+      .accounts({
+        pirate: piratePda,
+        emissionBudget: emissionBudget(1),
+        adminSet: null,
+        proposal: null,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    const treasureId = new anchor.BN(43);
+    try {
+      const att = attest(1, authority.publicKey, treasureId, 0);
+      await program.methods
+        .rewardTreasureFound(0, treasureId, att.nonce, att.expiresAt)
+        .accounts({
+          pirate: piratePda,
+          roles: rolesPda,
+          rewardSchedule: rewardSchedulePda,
+          emissionBudget: emissionBudget(1),
+          receipt: treasureReceipt(authority.publicKey, treasureId),
+          attestationReceipt: attestationReceipt(att.nonce),
+          mint: mintKeypair.publicKey,
+          fromVault: playerVault,
+          fromVaultTokens: vaultTokens(playerVault),
+          authority: authority.publicKey,
+        })
+        .preInstructions([att.ix])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("EmissionBudgetExhausted");
+    }
+  });
+
+  it("Should enforce the max supply", async () => {
+    const pirate = await program.account.pirate.fetch(piratePda);
+    await program.methods
+      .setMaxSupply(pirate.totalSupply)
+      .accounts({ pirate: piratePda, adminSet: null, proposal: null, authority: authority.publicKey })
+      .rpc();
+
+    try {
+      const att = attest(0, authority.publicKey, new anchor.BN(2));
+      await program.methods
+        .rewardLevelCompletion(2, att.nonce, att.expiresAt)
+        .accounts({
+          pirate: piratePda,
+          roles: rolesPda,
+          rewardSchedule: rewardSchedulePda,
+          emissionBudget: emissionBudget(0),
+          receipt: levelReceipt(authority.publicKey, 2),
+          attestationReceipt: attestationReceipt(att.nonce),
+          mint: mintKeypair.publicKey,
+          fromVault: playerVault,
+          fromVaultTokens: vaultTokens(playerVault),
+          authority: authority.publicKey,
+        })
+        .preInstructions([att.ix])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("MaxSupplyExceeded");
+    }
+
+    await program.methods
+      .setMaxSupply(null)
+      .accounts({ pirate: piratePda, adminSet: null, proposal: null, authority: authority.publicKey })
+      .rpc();
+  });
+
+  describe("Staking", () => {
+    const poolId = new anchor.BN(1);
+    let poolPda: PublicKey;
+
+    const poolAccount = (seed: string) =>
+      PublicKey.findProgramAddressSync([Buffer.from(seed), poolPda.toBuffer()], program.programId)[0];
+
+    const positionPda = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_position"), poolPda.toBuffer(), playerVault.toBuffer()],
+        program.programId
+      )[0];
+
+    const stakeAccounts = () => ({
+      pirate: piratePda,
+      pool: poolPda,
+      position: positionPda(),
+      fromVault: playerVault,
+      fromVaultTokens: vaultTokens(playerVault),
+      stakeTokens: poolAccount("stake_tokens"),
+      owner: authority.publicKey,
+    });
+
+    before(async () => {
+      [poolPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_pool"), piratePda.toBuffer(), poolId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createPool({
+          poolId,
+          rewardRate: new anchor.BN(10),
+          rewardSource: { mint: {} },
+          lockPeriod: new anchor.BN(3600),
+          lockBoosts: [{ duration: new anchor.BN(7200), boostBps: 5000 }],
+          earlyUnstakePenaltyBps: 1000,
+        })
+        .accounts({
+          pirate: piratePda,
+          adminSet: null,
+          proposal: null,
+          pool: poolPda,
+          mint: mintKeypair.publicKey,
+          stakeTokens: poolAccount("stake_tokens"),
+          rewardTokens: poolAccount("stake_rewards"),
+          penaltyTreasury: null,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Should stake tokens from a vault with a lock boost", async () => {
+      await program.methods.stake(new anchor.BN(50), 0).accounts(stakeAccounts()).rpc();
+
+      const position = await program.account.stakePosition.fetch(positionPda());
+      expect(position.amount.toNumber()).to.equal(50);
+      expect(position.weight.toNumber()).to.equal(75);
+      const staked = await getAccount(provider.connection, poolAccount("stake_tokens"));
+      expect(Number(staked.amount)).to.equal(50);
+    });
+
+    it("Should reject unknown lock boosts", async () => {
+      try {
+        await program.methods.stake(new anchor.BN(1), 3).accounts(stakeAccounts()).rpc();
+        expect.fail("Should have thrown error");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidLockBoost");
+      }
+    });
+
+    it("Should accrue and pay staking rewards", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+      const balanceBefore = await vaultBalance(playerVault);
+      await program.methods
+        .claimRewards()
+        .accounts({
+          pirate: piratePda,
+          mint: mintKeypair.publicKey,
+          pool: poolPda,
+          position: positionPda(),
+          vault: playerVault,
+          vaultTokens: vaultTokens(playerVault),
+          rewardTokens: poolAccount("stake_rewards"),
+          owner: authority.publicKey,
+        })
+        .rpc();
+      const balanceAfter = await vaultBalance(playerVault);
+      expect(balanceAfter.sub(balanceBefore).toNumber()).to.be.greaterThan(0);
+    });
+
+    it("Should burn the penalty when unstaking early", async () => {
+      const balanceBefore = await vaultBalance(playerVault);
+      const supplyBefore = (await program.account.pirate.fetch(piratePda)).totalSupply;
+      await program.methods
+        .unstake(new anchor.BN(50))
+        .accounts({
+          pirate: piratePda,
+          mint: mintKeypair.publicKey,
+          pool: poolPda,
+          position: positionPda(),
+          vault: playerVault,
+          vaultTokens: vaultTokens(playerVault),
+          stakeTokens: poolAccount("stake_tokens"),
+          penaltyTreasury: null,
+          owner: authority.publicKey,
+        })
+        .rpc();
+      const balanceAfter = await vaultBalance(playerVault);
+      const supplyAfter = (await program.account.pirate.fetch(piratePda)).totalSupply;
+      expect(balanceAfter.sub(balanceBefore).toNumber()).to.equal(45);
+      expect(supplyBefore.sub(supplyAfter).toNumber()).to.equal(5);
+    });
+  });
+
+  describe("Vesting", () => {
+    const vestingId = new anchor.BN(1);
+    let schedulePda: PublicKey;
+    let treasury: PublicKey;
+
+    const vestingTokens = () =>
+      PublicKey.findProgramAddressSync([Buffer.from("vesting_tokens"), schedulePda.toBuffer()], program.programId)[0];
+
+    const release = () =>
+      program.methods
+        .release()
+        .accounts({
+          pirate: piratePda,
+          schedule: schedulePda,
+          beneficiaryVault: player2Vault,
+          beneficiaryTokens: vaultTokens(player2Vault),
+          vestingTokens: vestingTokens(),
+          cranker: authority.publicKey,
+        })
+        .rpc();
+
+    before(async () => {
+      // The admin funds grants from a wallet token account, which is also the revocation treasury
+      treasury = (
+        await getOrCreateAssociatedTokenAccount(provider.connection, authority.payer, mintKeypair.publicKey, authority.publicKey)
+      ).address;
+      await program.methods
+        .withdrawPirateTokens(new anchor.BN(40))
+        .accounts({
+          pirate: piratePda,
+          vault: playerVault,
+          vaultTokens: vaultTokens(playerVault),
+          destination: treasury,
+          owner: authority.publicKey,
+        })
+        .rpc();
+
+      [schedulePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vesting"), piratePda.toBuffer(), player2Vault.toBuffer(), vestingId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const now = Math.floor(Date.now() / 1000);
+      await program.methods
+        .createVesting({
+          vestingId,
+          startTs: new anchor.BN(now),
+          cliffTs: new anchor.BN(now + 1_000_000),
+          endTs: new anchor.BN(now + 2_000_000),
+          linearAmount: new anchor.BN(10),
+          milestones: [new anchor.BN(10), new anchor.BN(20)],
+          revocable: true,
+        })
+        .accounts({
+          pirate: piratePda,
+          adminSet: null,
+          proposal: null,
+          schedule: schedulePda,
+          beneficiaryVault: player2Vault,
+          mint: mintKeypair.publicKey,
+          vestingTokens: vestingTokens(),
+          source: treasury,
+          treasury,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Should not release anything before the cliff", async () => {
+      try {
+        await release();
+        expect.fail("Should have thrown error");
+      } catch (err) {
+        expect(err.toString()).to.include("NothingToRelease");
+      }
+    });
+
+    it("Should release an unlocked milestone to the beneficiary", async () => {
+      await program.methods
+        .unlockMilestone(0)
+        .accounts({ pirate: piratePda, adminSet: null, proposal: null, schedule: schedulePda, authority: authority.publicKey })
+        .rpc();
+
+      const balanceBefore = await vaultBalance(player2Vault);
+      await release();
+      const balanceAfter = await vaultBalance(player2Vault);
+      expect(balanceAfter.sub(balanceBefore).toNumber()).to.equal(10);
+    });
+
+    it("Should return unvested tokens to the treasury on revoke", async () => {
+      const treasuryBefore = Number((await getAccount(provider.connection, treasury)).amount);
+      await program.methods
+        .revokeVesting()
+        .accounts({
+          pirate: piratePda,
+          adminSet: null,
+          proposal: null,
+          schedule: schedulePda,
+          vestingTokens: vestingTokens(),
+          treasury,
+          authority: authority.publicKey,
+        })
+        .rpc();
+      const treasuryAfter = Number((await getAccount(provider.connection, treasury)).amount);
+      expect(treasuryAfter - treasuryBefore).to.equal(30);
+      expect(Number((await getAccount(provider.connection, vestingTokens())).amount)).to.equal(0);
+    });
+  });
+
+  describe("Delegated spending", () => {
+    const allowancePda = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("allowance"), playerVault.toBuffer(), player2.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const approve = (amount: number, expiresAt: anchor.BN | null) =>
+      program.methods
+        .approve(player2.publicKey, new anchor.BN(amount), expiresAt)
+        .accounts({ vault: playerVault, allowance: allowancePda(), owner: authority.publicKey })
+        .rpc();
+
+    const delegatedTransfer = (amount: number) =>
+      program.methods
+        .transferPirateTokens(new anchor.BN(amount))
+        .accounts({
+          pirate: piratePda,
+          fromVault: playerVault,
+          fromVaultTokens: vaultTokens(playerVault),
+          toVault: player2Vault,
+          toVaultTokens: vaultTokens(player2Vault),
+          allowance: allowancePda(),
+          authority: player2.publicKey,
+        })
+        .signers([player2])
+        .rpc();
+
+    it("Should let a delegate spend within its allowance", async () => {
+      await approve(5, null);
+      const balanceBefore = await vaultBalance(player2Vault);
+      await delegatedTransfer(3);
+      const balanceAfter = await vaultBalance(player2Vault);
+      expect(balanceAfter.sub(balanceBefore).toNumber()).to.equal(3);
+
+      const allowance = await program.account.allowance.fetch(allowancePda());
+      expect(allowance.amount.toNumber()).to.equal(2);
+
+      try {
+        await delegatedTransfer(3);
+        expect.fail("Should have thrown error");
+      } catch (err) {
+        expect(err.toString()).to.include("AllowanceExceeded");
+      }
+    });
+
+    it("Should reject spending with an expired allowance", async () => {
+      await approve(5, new anchor.BN(Math.floor(Date.now() / 1000) - 60));
+      try {
+        await delegatedTransfer(1);
+        expect.fail("Should have thrown error");
+      } catch (err) {
+        expect(err.toString()).to.include("AllowanceExpired");
+      }
+    });
+
+    it("Should close the allowance on revoke", async () => {
+      await program.methods
+        .revoke()
+        .accounts({ vault: playerVault, allowance: allowancePda(), owner: authority.publicKey })
+        .rpc();
+      expect(await provider.connection.getAccountInfo(allowancePda())).to.be.null;
+    });
+  });
+
+  describe("Season airdrop", () => {
+    let treasury: PublicKey;
+
+    const leaf = (index: number, claimant: PublicKey, amount: number) =>
+      Buffer.from(
+        keccak_256(
+          Buffer.concat([
+            new anchor.BN(index).toArrayLike(Buffer, "le", 8),
+            claimant.toBuffer(),
+            new anchor.BN(amount).toArrayLike(Buffer, "le", 8),
+          ])
+        )
+      );
+
+    const hashPair = (a: Buffer, b: Buffer) =>
+      Buffer.from(keccak_256(Buffer.compare(a, b) <= 0 ? Buffer.concat([a, b]) : Buffer.concat([b, a])));
+
+    // Two players: the admin gets 100 at leaf 0, player2 gets 50 at leaf 1
+    const leaves = () => [leaf(0, authority.publicKey, 100), leaf(1, player2.publicKey, 50)];
+
+    const distributorPda = (id: anchor.BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("distributor"), piratePda.toBuffer(), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+
+    const distributorTokens = (distributor: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("distributor_tokens"), distributor.toBuffer()], program.programId)[0];
+
+    const createDistributor = async (id: anchor.BN, clawbackTs: number) => {
+      const [left, right] = leaves();
+      await program.methods
+        .createDistributor({
+          distributorId: id,
+          root: Array.from(hashPair(left, right)),
+          totalAmount: new anchor.BN(150),
+          numNodes: new anchor.BN(2),
+          clawbackTs: new anchor.BN(clawbackTs),
+        })
+        .accounts({
+          pirate: piratePda,
+          adminSet: null,
+          proposal: null,
+          distributor: distributorPda(id),
+          mint: mintKeypair.publicKey,
+          distributorTokens: distributorTokens(distributorPda(id)),
+          clawbackTreasury: treasury,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    };
+
+    const claim = (id: anchor.BN, index: number, amount: number, proof: Buffer[]) =>
+      program.methods
+        .claim(new anchor.BN(index), new anchor.BN(amount), proof.map((node) => Array.from(node)))
+        .accounts({
+          pirate: piratePda,
+          distributor: distributorPda(id),
+          distributorTokens: distributorTokens(distributorPda(id)),
+          vault: playerVault,
+          vaultTokens: vaultTokens(playerVault),
+          claimant: authority.publicKey,
+        })
+        .rpc();
+
+    const clawback = (id: anchor.BN) =>
+      program.methods
+        .clawback()
+        .accounts({
+          pirate: piratePda,
+          adminSet: null,
+          proposal: null,
+          distributor: distributorPda(id),
+          distributorTokens: distributorTokens(distributorPda(id)),
+          clawbackTreasury: treasury,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+    before(async () => {
+      treasury = (
+        await getOrCreateAssociatedTokenAccount(provider.connection, authority.payer, mintKeypair.publicKey, authority.publicKey)
+      ).address;
+    });
+
+    it("Should pay a claim with a valid proof exactly once", async () => {
+      const id = new anchor.BN(1);
+      await createDistributor(id, Math.floor(Date.now() / 1000) + 3600);
+
+      const balanceBefore = await vaultBalance(playerVault);
+      await claim(id, 0, 100, [leaves()[1]]);
+      const balanceAfter = await vaultBalance(playerVault);
+      expect(balanceAfter.sub(balanceBefore).toNumber()).to.equal(100);
+
+      try {
+        await claim(id, 0, 100, [leaves()[1]]);
+        expect.fail("Should have thrown error");
+      } catch (err) {
+        expect(err.toString()).to.include("AirdropAlreadyClaimed");
+      }
+
+      try {
+        await clawback(id);
+        expect.fail("Should have thrown error");
+      } catch (err) {
+        expect(err.toString()).to.include("ClawbackNotStarted");
+      }
+    });
+
+    it("Should reject an invalid proof", async () => {
+      try {
+        await claim(new anchor.BN(1), 1, 50, [leaves()[0]]);
+        expect.fail("Should have thrown error");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidMerkleProof");
+      }
+    });
+
+    it("Should claw back unclaimed tokens after the deadline", async () => {
+      const id = new anchor.BN(2);
+      await createDistributor(id, Math.floor(Date.now() / 1000) + 2);
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+
+      const treasuryBefore = Number((await getAccount(provider.connection, treasury)).amount);
+      await clawback(id);
+      const treasuryAfter = Number((await getAccount(provider.connection, treasury)).amount);
+      expect(treasuryAfter - treasuryBefore).to.equal(150);
+
+      try {
+        await claim(id, 0, 100, [leaves()[1]]);
+        expect.fail("Should have thrown error");
+      } catch (err) {
+        expect(err.toString()).to.include("DistributorClawedBack");
+      }
+    });
+  });
+
+  it("Should only close an empty vault", async () => {
+    const closeVault = (vault: PublicKey, owner: Keypair) =>
+      program.methods
+        .closeVault()
+        .accounts({ pirate: piratePda, vault, vaultTokens: vaultTokens(vault), owner: owner.publicKey })
+        .signers([owner])
+        .rpc();
+
+    try {
+      await closeVault(player2Vault, player2);
+      expect.fail("Should have thrown error");
+    } catch (err) {
+      expect(err.toString()).to.include("VaultNotEmpty");
+    }
+
+    const player3 = Keypair.generate();
+    const airdrop = await provider.connection.requestAirdrop(player3.publicKey, 1_000_000_000);
+    await provider.connection.confirmTransaction(airdrop);
+    const player3Vault = vaultPda(player3.publicKey);
+    await program.methods
+      .initializeVault()
+      .accounts({
+        vault: player3Vault,
+        pirate: piratePda,
+        mint: mintKeypair.publicKey,
+        vaultTokens: vaultTokens(player3Vault),
+        owner: player3.publicKey,
+      })
+      .signers([player3])
+      .rpc();
+    await closeVault(player3Vault, player3);
+    expect(await provider.connection.getAccountInfo(player3Vault)).to.be.null;
+    expect(await provider.connection.getAccountInfo(vaultTokens(player3Vault))).to.be.null;
+  });
+
+  describe("Admin handover and admin set", () => {
+    const adminSetPda = () =>
+      PublicKey.findProgramAddressSync([Buffer.from("admin_set"), piratePda.toBuffer()], program.programId)[0];
+
+    const proposalPda = (id: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("proposal"), piratePda.toBuffer(), new anchor.BN(id).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+
+    const liftMaxSupply = (proposal: PublicKey | null) =>
+      program.methods
+        .setMaxSupply(null)
+        .accounts({
+          pirate: piratePda,
+          adminSet: proposal ? adminSetPda() : null,
+          proposal,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+    it("Should hand the admin role over in two steps", async () => {
+      await program.methods
+        .proposeAuthority(player2.publicKey)
+        .accounts({ pirate: piratePda, authority: authority.publicKey })
+        .rpc();
+      await program.methods
+        .acceptAuthority()
+        .accounts({ pirate: piratePda, newAuthority: player2.publicKey })
+        .signers([player2])
+        .rpc();
+      expect((await program.account.pirate.fetch(piratePda)).authority.toString()).to.equal(player2.publicKey.toString());
+
+      await program.methods
+        .proposeAuthority(authority.publicKey)
+        .accounts({ pirate: piratePda, authority: player2.publicKey })
+        .signers([player2])
+        .rpc();
+      await program.methods
+        .acceptAuthority()
+        .accounts({ pirate: piratePda, newAuthority: authority.publicKey })
+        .rpc();
+      const pirate = await program.account.pirate.fetch(piratePda);
+      expect(pirate.authority.toString()).to.equal(authority.publicKey.toString());
+      expect(pirate.pendingAuthority).to.be.null;
+    });
+
+    it("Should require 2-of-2 approval for sensitive instructions once an admin set exists", async () => {
+      await program.methods
+        .initializeAdminSet([authority.publicKey, player2.publicKey], 2)
+        .accounts({ pirate: piratePda, adminSet: adminSetPda(), authority: authority.publicKey })
+        .rpc();
+
+      try {
+        await liftMaxSupply(null);
+        expect.fail("Should have thrown error");
+      } catch (err) {
+        expect(err.toString()).to.include("ProposalRequired");
+      }
+
+      // set_max_supply(None): the borsh encoding of None is a single zero byte
+      await program.methods
+        .createProposal({ setMaxSupply: {} }, Array.from(keccak_256(Buffer.from([0]))))
+        .accounts({
+          pirate: piratePda,
+          adminSet: adminSetPda(),
+          proposal: proposalPda(0),
+          proposer: authority.publicKey,
+        })
+        .rpc();
+
+      try {
+        await liftMaxSupply(proposalPda(0));
+        expect.fail("Should have thrown error");
+      } catch (err) {
+        expect(err.toString()).to.include("ProposalNotApproved");
+      }
+
+      await program.methods
+        .approveProposal()
+        .accounts({ pirate: piratePda, adminSet: adminSetPda(), proposal: proposalPda(0), member: player2.publicKey })
+        .signers([player2])
+        .rpc();
+      await liftMaxSupply(proposalPda(0));
+
+      try {
+        await liftMaxSupply(proposalPda(0));
+        expect.fail("Should have thrown error");
+      } catch (err) {
+        expect(err.toString()).to.include("ProposalAlreadyUsed");
+      }
+    });
+
+    it("Should route role changes through the admin set as well", async () => {
+      const grantMinter = (proposal: PublicKey | null) =>
+        program.methods
+          .addRole({ minter: {} }, player2.publicKey)
+          .accounts({
+            pirate: piratePda,
+            adminSet: proposal ? adminSetPda() : null,
+            proposal,
+            roles: rolesPda,
+            authority: authority.publicKey,
+          })
+          .rpc();
+
+      try {
+        await grantMinter(null);
+        expect.fail("Should have thrown error");
+      } catch (err) {
+        expect(err.toString()).to.include("ProposalRequired");
+      }
+
+      // add_role(Role::Minter, member): the enum index byte followed by the member key
+      const args = Buffer.concat([Buffer.from([0]), player2.publicKey.toBuffer()]);
+      await program.methods
+        .createProposal({ addRole: {} }, Array.from(keccak_256(args)))
+        .accounts({
+          pirate: piratePda,
+          adminSet: adminSetPda(),
+          proposal: proposalPda(1),
+          proposer: authority.publicKey,
+        })
+        .rpc();
+      await program.methods
+        .approveProposal()
+        .accounts({ pirate: piratePda, adminSet: adminSetPda(), proposal: proposalPda(1), member: player2.publicKey })
+        .signers([player2])
+        .rpc();
+      await grantMinter(proposalPda(1));
+
+      const roles = await program.account.roleConfig.fetch(rolesPda);
+      expect(roles.minters.map((key) => key.toString())).to.include(player2.publicKey.toString());
+    });
+
+    it("Should bind an approved pool to the penalty treasury it was proposed with", async () => {
+      const poolId = new anchor.BN(7);
+      const [poolPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_pool"), piratePda.toBuffer(), poolId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const poolAccount = (seed: string) =>
+        PublicKey.findProgramAddressSync([Buffer.from(seed), poolPda.toBuffer()], program.programId)[0];
+
+      const attacker = Keypair.generate();
+      const airdrop = await provider.connection.requestAirdrop(attacker.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+      const treasury = (
+        await getOrCreateAssociatedTokenAccount(provider.connection, authority.payer, mintKeypair.publicKey, authority.publicKey)
+      ).address;
+      const attackerTreasury = (
+        await getOrCreateAssociatedTokenAccount(provider.connection, authority.payer, mintKeypair.publicKey, attacker.publicKey)
+      ).address;
+
+      const params = {
+        poolId,
+        rewardRate: new anchor.BN(1),
+        rewardSource: { mint: {} },
+        lockPeriod: new anchor.BN(0),
+        lockBoosts: [],
+        earlyUnstakePenaltyBps: 500,
+      };
+      // (CreatePoolParams, Some(penalty_treasury)) in borsh: no lock boosts, RewardSource::Mint
+      const args = Buffer.concat([
+        poolId.toArrayLike(Buffer, "le", 8),
+        new anchor.BN(1).toArrayLike(Buffer, "le", 8),
+        Buffer.from([0]),
+        new anchor.BN(0).toArrayLike(Buffer, "le", 8),
+        Buffer.from([0, 0, 0, 0]),
+        new anchor.BN(500).toArrayLike(Buffer, "le", 2),
+        Buffer.from([1]),
+        treasury.toBuffer(),
+      ]);
+      await program.methods
+        .createProposal({ createPool: {} }, Array.from(keccak_256(args)))
+        .accounts({
+          pirate: piratePda,
+          adminSet: adminSetPda(),
+          proposal: proposalPda(2),
+          proposer: authority.publicKey,
+        })
+        .rpc();
+      await program.methods
+        .approveProposal()
+        .accounts({ pirate: piratePda, adminSet: adminSetPda(), proposal: proposalPda(2), member: player2.publicKey })
+        .signers([player2])
+        .rpc();
+
+      const createPool = (penaltyTreasury: PublicKey, signer: Keypair) =>
+        program.methods
+          .createPool(params)
+          .accounts({
+            pirate: piratePda,
+            adminSet: adminSetPda(),
+            proposal: proposalPda(2),
+            pool: poolPda,
+            mint: mintKeypair.publicKey,
+            stakeTokens: poolAccount("stake_tokens"),
+            rewardTokens: poolAccount("stake_rewards"),
+            penaltyTreasury,
+            authority: signer.publicKey,
+          })
+          .signers([signer])
+          .rpc();
+
+      // Anyone may execute an approved proposal, but not redirect its penalties
+      try {
+        await createPool(attackerTreasury, attacker);
+        expect.fail("Should have thrown error");
+      } catch (err) {
+        expect(err.toString()).to.include("ProposalMismatch");
+      }
+
+      await createPool(treasury, attacker);
+      const pool = await program.account.stakePool.fetch(poolPda);
+      expect(pool.penaltyTreasury.toString()).to.equal(treasury.toString());
+    });
+  });
+
   after(() => {
     console.log("\n🏴‍☠️ All token economy tests completed!");
   });